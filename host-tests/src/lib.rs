@@ -0,0 +1,47 @@
+//! Modules du noyau compilés pour l'hôte.
+//!
+//! `allocator` et `fat32` ne dépendent que de `core`, `alloc` et `spin` : on
+//! les inclut tels quels depuis `../src`, sans l'allocateur global, le VGA ni
+//! le port série du noyau. Les tests de `tests/` les exercent sur des heaps
+//! et des images disque en mémoire (`cargo +stable test`, éventuellement
+//! `--features buddy`, `slab_debug`…).
+
+extern crate alloc;
+
+#[path = "../../src"]
+mod kernel {
+    pub mod allocator;
+    pub mod fat32;
+}
+
+pub use kernel::{allocator, fat32};
+
+/// Remplace `serial_print!` du noyau : écrit sur la sortie standard.
+#[macro_export]
+macro_rules! serial_print {
+    ($($arg:tt)*) => { print!($($arg)*) };
+}
+
+/// Remplace `serial_println!` du noyau : écrit sur la sortie standard.
+#[macro_export]
+macro_rules! serial_println {
+    ($($arg:tt)*) => { println!($($arg)*) };
+}
+
+/// Remplace le port série du noyau (`dump_trace`, `dump_leaked_blocks`, rapports `kasan`).
+pub mod serial {
+    use core::fmt;
+    use spin::Mutex;
+
+    /// Sortie standard vue comme un `fmt::Write`.
+    pub struct Stdout;
+
+    impl fmt::Write for Stdout {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            print!("{}", s);
+            Ok(())
+        }
+    }
+
+    pub static SERIAL1: Mutex<Stdout> = Mutex::new(Stdout);
+}
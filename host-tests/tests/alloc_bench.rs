@@ -0,0 +1,30 @@
+//! Pendant hôte de `tests/alloc_bench.rs`, sur le même banc
+//! (`tests/bench/mod.rs`). Ignoré par défaut :
+//! `cargo +stable test --release --test alloc_bench -- --ignored --nocapture`.
+#![cfg(target_arch = "x86_64")]
+
+use core::alloc::Layout;
+
+use host_tests::allocator::{SimpleAllocator, N_SLABS, PAGE_SIZE};
+
+#[path = "../../tests/bench/mod.rs"]
+mod bench;
+
+const HEAP_SIZE: usize = 1024 * 1024;
+const ROUNDS: u64 = 100_000;
+
+#[test]
+#[ignore]
+fn alloc_free_cycles() {
+    let layout = Layout::from_size_align(HEAP_SIZE, PAGE_SIZE).unwrap();
+    let heap = unsafe { std::alloc::alloc(layout) };
+    assert!(!heap.is_null());
+    let after = SimpleAllocator::new();
+    unsafe { after.init(heap as usize, HEAP_SIZE) };
+    let before = bench::LockedInit::<_, N_SLABS>::new(&after);
+
+    let mut table = String::new();
+    bench::write_table(&before, &after, ROUNDS, &mut table).unwrap();
+    println!("\n{}", table);
+    unsafe { std::alloc::dealloc(heap, layout) };
+}
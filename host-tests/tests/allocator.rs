@@ -0,0 +1,483 @@
+//! Tests de propriétés de l'allocateur : des suites aléatoires d'allocations,
+//! de `realloc` et de libérations sont rejouées sur un heap neuf et comparées
+//! à un modèle (liste des blocs vivants et de leur contenu attendu).
+
+use core::alloc::{GlobalAlloc, Layout};
+use proptest::prelude::*;
+
+use host_tests::allocator::{HeapSnapshot, PAGE_SIZE};
+
+/// Taille du heap de chaque cas.
+const HEAP_SIZE: usize = 4 * 1024 * 1024;
+/// Nombre maximal de blocs vivants, pour que le heap ne s'épuise jamais.
+const MAX_LIVE: usize = 64;
+
+#[derive(Debug, Clone)]
+enum Op {
+    Alloc { size: usize, align_shift: u32, zeroed: bool },
+    Realloc { index: usize, size: usize },
+    Dealloc { index: usize },
+}
+
+fn size() -> impl Strategy<Value = usize> {
+    prop_oneof![1..=16usize, 1..=128usize, 129..=4096usize, 4097..=3 * PAGE_SIZE]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => (size(), 0..=12u32, any::<bool>())
+            .prop_map(|(size, align_shift, zeroed)| Op::Alloc { size, align_shift, zeroed }),
+        1 => (any::<usize>(), size()).prop_map(|(index, size)| Op::Realloc { index, size }),
+        2 => any::<usize>().prop_map(|index| Op::Dealloc { index }),
+    ]
+}
+
+/// Bloc vivant du modèle : tous ses octets valent `tag`.
+struct Block {
+    ptr: *mut u8,
+    layout: Layout,
+    tag: u8,
+}
+
+/// Heap de `HEAP_SIZE` octets pris à l'allocateur de l'hôte, aligné sur une
+/// page mais décalé d'un octet pour exercer l'alignement du début du heap.
+struct Heap {
+    base: *mut u8,
+}
+
+impl Heap {
+    fn layout() -> Layout {
+        Layout::from_size_align(HEAP_SIZE + PAGE_SIZE, PAGE_SIZE).unwrap()
+    }
+
+    fn new() -> Self {
+        let base = unsafe { std::alloc::alloc(Self::layout()) };
+        assert!(!base.is_null());
+        Heap { base }
+    }
+
+    fn start(&self) -> usize {
+        self.base as usize + 1
+    }
+}
+
+impl Drop for Heap {
+    fn drop(&mut self) {
+        unsafe { std::alloc::dealloc(self.base, Self::layout()) }
+    }
+}
+
+/// Vérifie que le bloc respecte son alignement et ne chevauche aucun autre.
+fn check_placement(block: &Block, live: &[Block]) {
+    let start = block.ptr as usize;
+    assert_eq!(start % block.layout.align(), 0, "bloc mal aligné: {:?}", block.layout);
+    let end = start + block.layout.size();
+    for other in live {
+        let other_start = other.ptr as usize;
+        let other_end = other_start + other.layout.size();
+        assert!(end <= other_start || other_end <= start, "blocs qui se chevauchent");
+    }
+}
+
+/// Vérifie que le contenu du bloc n'a pas été modifié.
+fn check_contents(block: &Block) {
+    let bytes = unsafe { core::slice::from_raw_parts(block.ptr, block.layout.size()) };
+    assert!(bytes.iter().all(|&b| b == block.tag), "contenu écrasé");
+}
+
+/// Rejoue `ops` sur `allocator` en le confrontant au modèle, libère tout ce
+/// qui reste et vérifie qu'aucun bloc n'a fui.
+fn run<A: GlobalAlloc, const CLASSES: usize>(allocator: &A, snapshot: impl Fn() -> HeapSnapshot<CLASSES>, ops: &[Op]) {
+    let before = snapshot();
+    let mut live: Vec<Block> = Vec::new();
+    for (i, op) in ops.iter().enumerate() {
+        let tag = i as u8;
+        match *op {
+            Op::Alloc { size, align_shift, zeroed } => {
+                if live.len() == MAX_LIVE {
+                    continue;
+                }
+                let layout = Layout::from_size_align(size, 1 << align_shift).unwrap();
+                let ptr = unsafe {
+                    if zeroed { allocator.alloc_zeroed(layout) } else { allocator.alloc(layout) }
+                };
+                assert!(!ptr.is_null(), "allocation refusée: {:?}", layout);
+                let block = Block { ptr, layout, tag };
+                check_placement(&block, &live);
+                if zeroed {
+                    check_contents(&Block { tag: 0, ..block });
+                }
+                unsafe { ptr.write_bytes(tag, size) };
+                live.push(block);
+            }
+            Op::Realloc { index, size } => {
+                if live.is_empty() {
+                    continue;
+                }
+                let block = live.swap_remove(index % live.len());
+                check_contents(&block);
+                let ptr = unsafe { allocator.realloc(block.ptr, block.layout, size) };
+                assert!(!ptr.is_null(), "realloc refusé: {:?} -> {}", block.layout, size);
+                let kept = block.layout.size().min(size);
+                let layout = Layout::from_size_align(size, block.layout.align()).unwrap();
+                let moved = Block { ptr, layout, tag: block.tag };
+                check_contents(&Block { layout: Layout::from_size_align(kept, 1).unwrap(), ..moved });
+                check_placement(&moved, &live);
+                unsafe { ptr.write_bytes(moved.tag, size) };
+                live.push(moved);
+            }
+            Op::Dealloc { index } => {
+                if live.is_empty() {
+                    continue;
+                }
+                let block = live.swap_remove(index % live.len());
+                check_contents(&block);
+                unsafe { allocator.dealloc(block.ptr, block.layout) };
+            }
+        }
+    }
+    for block in &live {
+        check_contents(block);
+    }
+    for block in live.drain(..) {
+        unsafe { allocator.dealloc(block.ptr, block.layout) };
+    }
+    let leaks = snapshot().leaks_since(&before);
+    assert!(leaks.is_empty(), "blocs non rendus: {}", leaks);
+}
+
+#[cfg(not(feature = "buddy"))]
+proptest! {
+    #[test]
+    fn slab_allocator_matches_model(ops in prop::collection::vec(op(), 1..400)) {
+        let heap = Heap::new();
+        let allocator = host_tests::allocator::SimpleAllocator::new();
+        unsafe { allocator.init(heap.start(), HEAP_SIZE) };
+        let initial = allocator.stats().large;
+        run(&allocator, || allocator.snapshot(), &ops);
+        #[cfg(feature = "magazines")]
+        allocator.drain_magazines();
+
+        // Chaque slab garde au plus sa dernière page ; tout le reste est
+        // revenu au second niveau.
+        let stats = allocator.stats();
+        let kept: usize = stats.slabs.iter().map(|slab| slab.pages * PAGE_SIZE).sum();
+        prop_assert!(stats.slabs.iter().all(|slab| slab.in_use == 0 && slab.pages <= 1));
+        prop_assert_eq!(stats.large.in_use, initial.in_use);
+        prop_assert_eq!(stats.large.free_bytes + kept, initial.free_bytes);
+    }
+}
+
+/// Classes de taille quelconques, pas toutes des puissances de deux.
+#[cfg(not(feature = "buddy"))]
+const CUSTOM_CLASSES: [host_tests::allocator::SizeClass; 5] = {
+    use host_tests::allocator::SizeClass;
+    [SizeClass::new(24), SizeClass::new(48), SizeClass::new(80), SizeClass::new(192), SizeClass::new(1024)]
+};
+
+#[cfg(not(feature = "buddy"))]
+proptest! {
+    #[test]
+    fn custom_classes_match_model(ops in prop::collection::vec(op(), 1..400)) {
+        let heap = Heap::new();
+        let allocator = host_tests::allocator::SlabAllocator::with_classes(CUSTOM_CLASSES);
+        unsafe { allocator.init(heap.start(), HEAP_SIZE) };
+        let initial = allocator.stats().large;
+        run(&allocator, || allocator.snapshot(), &ops);
+        #[cfg(feature = "magazines")]
+        allocator.drain_magazines();
+
+        let stats = allocator.stats();
+        prop_assert!(stats.slabs.iter().all(|slab| slab.in_use == 0 && slab.pages <= 1));
+        prop_assert_eq!(stats.large.in_use, initial.in_use);
+    }
+}
+
+#[cfg(feature = "buddy")]
+proptest! {
+    #[test]
+    fn buddy_allocator_matches_model(ops in prop::collection::vec(op(), 1..400)) {
+        let heap = Heap::new();
+        let allocator = host_tests::allocator::BuddyAllocator::new();
+        unsafe { allocator.init(heap.start(), HEAP_SIZE) };
+        let initial = allocator.stats();
+        run(&allocator, || allocator.snapshot(), &ops);
+
+        // Tous les buddies libres ont été refusionnés.
+        let stats = allocator.stats();
+        prop_assert_eq!(stats.in_use, 0);
+        prop_assert_eq!(stats.free_bytes, initial.free_bytes);
+        prop_assert_eq!(stats.free_blocks, initial.free_blocks);
+    }
+}
+
+#[cfg(not(feature = "buddy"))]
+#[test]
+fn pages_are_aligned_and_returned() {
+    let heap = Heap::new();
+    let allocator = host_tests::allocator::SimpleAllocator::new();
+    unsafe { allocator.init(heap.start(), HEAP_SIZE) };
+    let initial = allocator.stats().large;
+    let pages = unsafe { allocator.alloc_pages(3) };
+    assert!(!pages.is_null());
+    assert_eq!(pages as usize % PAGE_SIZE, 0);
+    unsafe { allocator.dealloc_pages(pages, 3) };
+    assert_eq!(allocator.stats().large.free_bytes, initial.free_bytes);
+}
+
+#[cfg(not(any(feature = "buddy", feature = "magazines")))]
+#[test]
+fn heap_map_reports_free_runs() {
+    let heap = Heap::new();
+    let allocator = host_tests::allocator::SimpleAllocator::new();
+    unsafe { allocator.init(heap.start(), HEAP_SIZE) };
+    let layout = Layout::from_size_align(24, 8).unwrap();
+    let blocks: Vec<*mut u8> = (0..9).map(|_| unsafe { allocator.alloc(layout) }).collect();
+    for &block in blocks.iter().step_by(2) {
+        unsafe { allocator.dealloc(block, layout) };
+    }
+
+    let mut map = String::new();
+    allocator.write_map(&mut map).unwrap();
+    let mut lines = map.lines().skip_while(|line| !line.starts_with("class=32 "));
+    let capacity = allocator.stats().slabs[1].total_blocks;
+    assert_eq!(
+        lines.next().unwrap(),
+        format!("class=32 pages=1 used=4/{0} free_list=5 longest_run={1} blocks_per_char=2", capacity, capacity - 8)
+    );
+    assert!(lines.next().unwrap().ends_with(&format!("used=4/{} free_list=5 longest_run={}", capacity, capacity - 8)));
+    let row = lines.next().unwrap().trim();
+    assert_eq!(row.len(), capacity.div_ceil(2));
+    assert!(row.starts_with("++++."));
+    assert!(row[4..].chars().all(|c| c == '.'));
+    for &block in blocks.iter().skip(1).step_by(2) {
+        unsafe { allocator.dealloc(block, layout) };
+    }
+}
+
+#[cfg(feature = "magazines")]
+#[test]
+fn magazines_refill_and_flush_in_batches() {
+    use host_tests::allocator::MAGAZINE_SIZE;
+
+    let heap = Heap::new();
+    let allocator = host_tests::allocator::SimpleAllocator::new();
+    unsafe { allocator.init(heap.start(), HEAP_SIZE) };
+    let layout = Layout::from_size_align(24, 8).unwrap();
+    let slab_line = |allocator: &host_tests::allocator::SimpleAllocator| {
+        let mut map = String::new();
+        allocator.write_map(&mut map).unwrap();
+        map.lines().find(|line| line.starts_with("class=32 ")).unwrap().to_owned()
+    };
+
+    // Une allocation recharge le magazine d'un lot de blocs.
+    let block = unsafe { allocator.alloc(layout) };
+    assert_eq!(allocator.stats().slabs[1].in_use, 1);
+    assert_eq!(allocator.stats().slabs[1].bytes_requested, 24);
+    assert!(slab_line(&allocator).contains(&format!(" used={}/", MAGAZINE_SIZE / 2)));
+    unsafe { allocator.dealloc(block, layout) };
+    assert_eq!(allocator.stats().slabs[1].in_use, 0);
+    assert_eq!(allocator.drain_magazines(), MAGAZINE_SIZE / 2);
+    assert!(slab_line(&allocator).contains(" used=0/"));
+
+    // Un magazine plein rend la moitié de ses blocs au slab.
+    let blocks: Vec<*mut u8> = (0..3 * MAGAZINE_SIZE).map(|_| unsafe { allocator.alloc(layout) }).collect();
+    assert_eq!(allocator.stats().slabs[1].in_use, blocks.len());
+    for &block in &blocks {
+        unsafe { allocator.dealloc(block, layout) };
+    }
+    let stats = allocator.stats().slabs[1];
+    assert_eq!((stats.in_use, stats.bytes_requested), (0, 0));
+    let drained = allocator.drain_magazines();
+    assert!(drained > MAGAZINE_SIZE / 2 && drained <= MAGAZINE_SIZE);
+    assert!(slab_line(&allocator).contains(" used=0/"));
+}
+
+#[cfg(not(feature = "buddy"))]
+#[test]
+fn try_alloc_reports_why_it_failed() {
+    use host_tests::allocator::AllocError;
+
+    let heap = Heap::new();
+    let allocator = host_tests::allocator::SimpleAllocator::new();
+    unsafe { allocator.init(heap.start(), HEAP_SIZE) };
+    assert_eq!(allocator.last_error(), None);
+
+    let misaligned = Layout::from_size_align(64, 2 * PAGE_SIZE).unwrap();
+    assert_eq!(allocator.try_alloc(misaligned), Err(AllocError::Misaligned));
+    let huge = Layout::from_size_align(2 * HEAP_SIZE, 8).unwrap();
+    assert_eq!(allocator.try_alloc(huge), Err(AllocError::TooLarge));
+    assert!(unsafe { allocator.alloc(huge) }.is_null());
+    assert_eq!(allocator.last_error(), Some(AllocError::TooLarge));
+
+    // Une fois le heap rempli de pages, ni les gros objets ni un slab sans
+    // page ne peuvent plus être servis.
+    let page = Layout::from_size_align(PAGE_SIZE, PAGE_SIZE).unwrap();
+    let mut pages = Vec::new();
+    while let Ok(ptr) = allocator.try_alloc(page) {
+        pages.push(ptr);
+    }
+    assert_eq!(allocator.last_error(), Some(AllocError::Exhausted(None)));
+    let small = Layout::from_size_align(24, 8).unwrap();
+    assert_eq!(allocator.try_alloc(small), Err(AllocError::Exhausted(Some(32))));
+
+    for ptr in pages {
+        unsafe { allocator.dealloc(ptr.as_ptr(), page) };
+    }
+    let block = allocator.try_alloc(small).unwrap();
+    unsafe { allocator.dealloc(block.as_ptr(), small) };
+}
+
+#[cfg(not(feature = "buddy"))]
+#[test]
+fn shrinkers_are_retried_but_not_reentered() {
+    use host_tests::allocator::{Shrinker, SimpleAllocator};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    static ALLOCATOR: SimpleAllocator = SimpleAllocator::new();
+    /// Pages gardées par le « cache » que vide `DROP_ONE`.
+    static CACHE: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+    /// Allocations tentées par `REENTRANT` pendant la passe, et leurs succès.
+    static NESTED: AtomicUsize = AtomicUsize::new(0);
+    static NESTED_OK: AtomicUsize = AtomicUsize::new(0);
+
+    fn page() -> Layout {
+        Layout::from_size_align(PAGE_SIZE, PAGE_SIZE).unwrap()
+    }
+    static REENTRANT: Shrinker = Shrinker {
+        name: "reentrant",
+        shrink: |_| {
+            let ptr = unsafe { ALLOCATOR.alloc(page()) };
+            NESTED.fetch_add(1, Ordering::Relaxed);
+            if !ptr.is_null() {
+                NESTED_OK.fetch_add(1, Ordering::Relaxed);
+            }
+            0
+        },
+    };
+    static DROP_ONE: Shrinker = Shrinker {
+        name: "drop_one",
+        shrink: |_| match CACHE.lock().unwrap().pop() {
+            Some(ptr) => {
+                unsafe { ALLOCATOR.dealloc(ptr as *mut u8, page()) };
+                PAGE_SIZE
+            }
+            None => 0,
+        },
+    };
+
+    let heap = Heap::new();
+    unsafe { ALLOCATOR.init(heap.start(), HEAP_SIZE) };
+    loop {
+        let ptr = unsafe { ALLOCATOR.alloc(page()) };
+        if ptr.is_null() {
+            break;
+        }
+        CACHE.lock().unwrap().push(ptr as usize);
+    }
+    ALLOCATOR.register_shrinker(&REENTRANT).unwrap();
+    ALLOCATOR.register_shrinker(&DROP_ONE).unwrap();
+
+    // L'allocation échoue, la passe rend une page et l'allocation est retentée ;
+    // celle du shrinker, faite pendant la passe, échoue sans la relancer.
+    let ptr = unsafe { ALLOCATOR.alloc(page()) };
+    assert!(!ptr.is_null());
+    assert_eq!(NESTED.load(Ordering::Relaxed), 1);
+    assert_eq!(NESTED_OK.load(Ordering::Relaxed), 0);
+    let stats = ALLOCATOR.shrinker_stats();
+    assert_eq!(stats[0].map(|stats| stats.runs), Some(1));
+    assert_eq!(stats[1].map(|stats| (stats.runs, stats.reclaimed)), Some((1, PAGE_SIZE)));
+
+    unsafe { ALLOCATOR.dealloc(ptr, page()) };
+    for ptr in CACHE.lock().unwrap().drain(..) {
+        unsafe { ALLOCATOR.dealloc(ptr as *mut u8, page()) };
+    }
+}
+
+#[cfg(feature = "fault_injection")]
+#[test]
+fn injected_faults_are_reported_as_such() {
+    use host_tests::allocator::{AllocError, Fault};
+
+    let heap = Heap::new();
+    let allocator = host_tests::allocator::SimpleAllocator::new();
+    unsafe { allocator.init(heap.start(), HEAP_SIZE) };
+    let small = Layout::from_size_align(24, 8).unwrap();
+    allocator.inject_faults(Fault::Every(1));
+    assert_eq!(allocator.try_alloc(small), Err(AllocError::Injected));
+    assert!(unsafe { allocator.alloc(Layout::from_size_align(1000, 8).unwrap()) }.is_null());
+    assert_eq!(allocator.last_error(), Some(AllocError::Injected));
+    assert_eq!(AllocError::Injected.to_string(), "injected fault");
+    assert_eq!(allocator.clear_faults(), 2);
+
+    let block = allocator.try_alloc(small).unwrap();
+    unsafe { allocator.dealloc(block.as_ptr(), small) };
+}
+
+#[cfg(feature = "buddy")]
+#[test]
+fn buddy_try_alloc_reports_why_it_failed() {
+    use host_tests::allocator::AllocError;
+
+    let heap = Heap::new();
+    let allocator = host_tests::allocator::BuddyAllocator::new();
+    unsafe { allocator.init(heap.start(), HEAP_SIZE) };
+    let misaligned = Layout::from_size_align(64, 2 * PAGE_SIZE).unwrap();
+    assert_eq!(allocator.try_alloc(misaligned), Err(AllocError::Misaligned));
+    let huge = Layout::from_size_align(2 * HEAP_SIZE, 8).unwrap();
+    assert_eq!(allocator.try_alloc(huge), Err(AllocError::TooLarge));
+
+    let half = Layout::from_size_align(HEAP_SIZE / 2, 8).unwrap();
+    let block = allocator.try_alloc(half).unwrap();
+    assert_eq!(allocator.try_alloc(half), Err(AllocError::Exhausted(Some(HEAP_SIZE / 2))));
+    assert_eq!(allocator.last_error(), Some(AllocError::Exhausted(Some(HEAP_SIZE / 2))));
+    unsafe { allocator.dealloc(block.as_ptr(), half) };
+}
+
+#[cfg(not(feature = "buddy"))]
+#[test]
+fn custom_classes_pick_size_alignment_and_capacity() {
+    use host_tests::allocator::{AllocError, SizeClass, SlabAllocator};
+
+    static ALLOCATOR: SlabAllocator<3> =
+        SlabAllocator::with_classes([SizeClass::new(24), SizeClass::new(48).with_max_pages(1), SizeClass::new(96)]);
+    let heap = Heap::new();
+    unsafe { ALLOCATOR.init(heap.start(), HEAP_SIZE) };
+    let class_of = |layout: Layout| {
+        let before = ALLOCATOR.stats();
+        let ptr = ALLOCATOR.try_alloc(layout).unwrap();
+        assert_eq!(ptr.as_ptr() as usize % layout.align(), 0);
+        let after = ALLOCATOR.stats();
+        let class = (0..3).find(|&i| after.slabs[i].in_use > before.slabs[i].in_use).map(|i| after.slabs[i].obj_size);
+        (ptr, class)
+    };
+
+    // 24 octets n'est aligné que sur 8 : une requête alignée sur 16 passe à 48.
+    // Les tailles laissent la place de la zone rouge de `slab_debug`.
+    let (a, class) = class_of(Layout::from_size_align(16, 8).unwrap());
+    assert_eq!(class, Some(24));
+    let (b, class) = class_of(Layout::from_size_align(16, 16).unwrap());
+    assert_eq!(class, Some(48));
+    let (c, class) = class_of(Layout::from_size_align(88, 32).unwrap());
+    assert_eq!(class, Some(96));
+    let (d, class) = class_of(Layout::from_size_align(100, 8).unwrap());
+    assert_eq!(class, None);
+
+    // La classe 48 est limitée à une page, même si le heap a de la place.
+    let layout = Layout::from_size_align(40, 16).unwrap();
+    let capacity = ALLOCATOR.stats().slabs[1].total_blocks;
+    let blocks: Vec<_> = (1..capacity).map(|_| ALLOCATOR.try_alloc(layout).unwrap()).collect();
+    assert_eq!(ALLOCATOR.try_alloc(layout), Err(AllocError::Exhausted(Some(48))));
+    assert_eq!(ALLOCATOR.stats().slabs[1].pages, 1);
+
+    unsafe {
+        for block in blocks {
+            ALLOCATOR.dealloc(block.as_ptr(), layout);
+        }
+        ALLOCATOR.dealloc(a.as_ptr(), Layout::from_size_align(16, 8).unwrap());
+        ALLOCATOR.dealloc(b.as_ptr(), Layout::from_size_align(16, 16).unwrap());
+        ALLOCATOR.dealloc(c.as_ptr(), Layout::from_size_align(88, 32).unwrap());
+        ALLOCATOR.dealloc(d.as_ptr(), Layout::from_size_align(100, 8).unwrap());
+    }
+}
@@ -0,0 +1,1033 @@
+//! Tests de propriétés du lecteur FAT32 : des images aléatoires (géométrie,
+//! fichiers, chaînes de clusters dispersées, entrées supprimées) sont lues par
+//! `Fat32` et par un parseur de référence écrit directement d'après la
+//! spécification, puis comparées au contenu qui a servi à les construire.
+//! Des écritures et suppressions aléatoires y sont ensuite appliquées et
+//! relues après avoir remonté le volume. Des arborescences aléatoires de
+//! sous-répertoires vérifient la résolution des chemins, et des noms longs
+//! VFAT aléatoires leur décodage et leur création.
+
+use proptest::prelude::*;
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+
+use host_tests::fat32::{BlockDevice, DirectoryEntry, Fat32, FatError, MemoryDisk};
+
+const SECTOR: usize = 512;
+/// Marqueur d'entrée de répertoire supprimée.
+const DELETED: u8 = 0xE5;
+/// Nombre maximal de clusters de données d'une image.
+const MAX_CLUSTERS: usize = 48;
+
+/// Disque en mémoire de taille quelconque.
+struct VecDisk(Vec<u8>);
+
+impl BlockDevice for VecDisk {
+    fn read_sector(&mut self, lba: u32, buf: &mut [u8; 512]) {
+        let start = lba as usize * SECTOR;
+        buf.copy_from_slice(&self.0[start..start + SECTOR]);
+    }
+
+    fn write_sector(&mut self, lba: u32, buf: &[u8; 512]) {
+        let start = lba as usize * SECTOR;
+        self.0[start..start + SECTOR].copy_from_slice(buf);
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Geometry {
+    reserved: u16,
+    fats: u8,
+    sectors_per_cluster: u8,
+    sectors_per_fat: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct File {
+    name: [u8; 11],
+    data: Vec<u8>,
+}
+
+/// Image à construire : les fichiers du répertoire racine, les entrées
+/// supprimées à intercaler et l'ordre dans lequel les clusters sont pris.
+#[derive(Debug, Clone)]
+struct Image {
+    geometry: Geometry,
+    files: Vec<File>,
+    deleted: Vec<bool>,
+    clusters: Vec<u32>,
+}
+
+fn geometry() -> impl Strategy<Value = Geometry> {
+    (1..=4u16, 1..=2u8, prop::sample::select(vec![1u8, 2, 4, 8]), 1..=2u32).prop_map(
+        |(reserved, fats, sectors_per_cluster, sectors_per_fat)| Geometry {
+            reserved,
+            fats,
+            sectors_per_cluster,
+            sectors_per_fat,
+        },
+    )
+}
+
+fn short_name() -> impl Strategy<Value = [u8; 11]> {
+    ("[A-Z0-9_]{1,8}", "[A-Z]{0,3}").prop_map(|(base, ext)| {
+        let mut name = [b' '; 11];
+        name[..base.len()].copy_from_slice(base.as_bytes());
+        name[8..8 + ext.len()].copy_from_slice(ext.as_bytes());
+        name
+    })
+}
+
+fn image() -> impl Strategy<Value = Image> {
+    geometry().prop_flat_map(|geometry| {
+        let cluster_size = SECTOR * geometry.sectors_per_cluster as usize;
+        let file = (short_name(), prop::collection::vec(any::<u8>(), 0..=cluster_size * 5 / 2))
+            .prop_map(|(name, data)| File { name, data });
+        let clusters: Vec<u32> = (2..2 + MAX_CLUSTERS as u32).collect();
+        (
+            Just(geometry),
+            prop::collection::vec(file, 0..=8),
+            prop::collection::vec(any::<bool>(), 0..=8),
+            Just(clusters).prop_shuffle(),
+        )
+            .prop_map(|(geometry, files, deleted, clusters)| Image { geometry, files, deleted, clusters })
+    })
+}
+
+impl Image {
+    fn cluster_size(&self) -> usize {
+        SECTOR * self.geometry.sectors_per_cluster as usize
+    }
+
+    /// Construit l'image disque. Chaque chaîne prend les clusters suivants
+    /// de `clusters`, donc dispersés sur le disque.
+    fn build(&self) -> Vec<u8> {
+        let g = &self.geometry;
+        let cluster_size = self.cluster_size();
+        let fat_start = g.reserved as usize * SECTOR;
+        let data_start = fat_start + g.fats as usize * g.sectors_per_fat as usize * SECTOR;
+        let mut disk = vec![0u8; data_start + MAX_CLUSTERS * cluster_size];
+
+        disk[11..13].copy_from_slice(&(SECTOR as u16).to_le_bytes());
+        disk[13] = g.sectors_per_cluster;
+        disk[14..16].copy_from_slice(&g.reserved.to_le_bytes());
+        disk[16] = g.fats;
+        let total_sectors = (disk.len() / SECTOR) as u32;
+        disk[32..36].copy_from_slice(&total_sectors.to_le_bytes());
+        disk[36..40].copy_from_slice(&g.sectors_per_fat.to_le_bytes());
+
+        let mut fat = vec![0u32; g.sectors_per_fat as usize * SECTOR / 4];
+        fat[0] = 0x0FFF_FFF8;
+        fat[1] = 0x0FFF_FFFF;
+        let mut free = self.clusters.iter().copied();
+        let mut chain = |len: usize, fat: &mut Vec<u32>| -> Vec<u32> {
+            let chain: Vec<u32> = free.by_ref().take(len).collect();
+            for (i, &cluster) in chain.iter().enumerate() {
+                // Fin de chaîne : n'importe quelle valeur de 0x?FFFFFF8 à 0x?FFFFFFF.
+                fat[cluster as usize] = chain.get(i + 1).copied().unwrap_or(0xFFFF_FFF8 | (i as u32 & 7));
+            }
+            chain
+        };
+
+        let mut dir = Vec::new();
+        let mut deleted = self.deleted.iter();
+        for file in &self.files {
+            if deleted.next() == Some(&true) {
+                let mut entry = [0u8; 32];
+                entry[0] = DELETED;
+                entry[1..11].copy_from_slice(b"OLD     TX");
+                dir.push(entry);
+            }
+            let clusters = chain(file.data.len().div_ceil(cluster_size), &mut fat);
+            for (cluster, data) in clusters.iter().zip(file.data.chunks(cluster_size)) {
+                let start = data_start + (*cluster as usize - 2) * cluster_size;
+                disk[start..start + data.len()].copy_from_slice(data);
+            }
+            let first = clusters.first().copied().unwrap_or(0);
+            let mut entry = [0u8; 32];
+            entry[0..11].copy_from_slice(&file.name);
+            entry[11] = 0x20;
+            entry[20..22].copy_from_slice(&((first >> 16) as u16).to_le_bytes());
+            entry[26..28].copy_from_slice(&(first as u16).to_le_bytes());
+            entry[28..32].copy_from_slice(&(file.data.len() as u32).to_le_bytes());
+            dir.push(entry);
+        }
+
+        let root = chain((dir.len() * 32).div_ceil(cluster_size).max(1), &mut fat);
+        disk[44..48].copy_from_slice(&root[0].to_le_bytes());
+        let dir: Vec<u8> = dir.concat();
+        for (cluster, data) in root.iter().zip(dir.chunks(cluster_size)) {
+            let start = data_start + (*cluster as usize - 2) * cluster_size;
+            disk[start..start + data.len()].copy_from_slice(data);
+        }
+
+        let fat: Vec<u8> = fat.iter().flat_map(|entry| entry.to_le_bytes()).collect();
+        for copy in 0..g.fats as usize {
+            let start = fat_start + copy * fat.len();
+            disk[start..start + fat.len()].copy_from_slice(&fat);
+        }
+        disk
+    }
+}
+
+/// Entrée lue par le parseur de référence.
+#[derive(Debug, PartialEq, Eq)]
+struct Entry {
+    name: [u8; 11],
+    attr: u8,
+    first_cluster: u32,
+    size: u32,
+    data: Vec<u8>,
+}
+
+/// Parseur de référence : lit le répertoire racine et le contenu de chaque
+/// fichier directement dans les octets de l'image. Les entrées de nom long
+/// sont ignorées.
+fn reference_parse(disk: &[u8]) -> Vec<Entry> {
+    let u16_at = |at: usize| u16::from_le_bytes([disk[at], disk[at + 1]]) as usize;
+    let u32_at = |at: usize| u32::from_le_bytes([disk[at], disk[at + 1], disk[at + 2], disk[at + 3]]);
+    let bytes_per_sector = u16_at(11);
+    let cluster_size = bytes_per_sector * disk[13] as usize;
+    let fat_start = u16_at(14) * bytes_per_sector;
+    let data_start = fat_start + disk[16] as usize * u32_at(36) as usize * bytes_per_sector;
+    let chain = |first: u32| {
+        let mut data = Vec::new();
+        let mut cluster = first;
+        while (2..0x0FFF_FFF8).contains(&cluster) {
+            let start = data_start + (cluster as usize - 2) * cluster_size;
+            data.extend_from_slice(&disk[start..start + cluster_size]);
+            cluster = u32_at(fat_start + cluster as usize * 4) & 0x0FFF_FFFF;
+        }
+        data
+    };
+
+    let root = chain(u32_at(44));
+    root.chunks(32)
+        .take_while(|entry| entry[0] != 0)
+        .filter(|entry| entry[0] != DELETED && entry[11] & 0x3F != 0x0F)
+        .map(|entry| {
+            let first_cluster = (u16::from_le_bytes([entry[20], entry[21]]) as u32) << 16
+                | u16::from_le_bytes([entry[26], entry[27]]) as u32;
+            let size = u32::from_le_bytes([entry[28], entry[29], entry[30], entry[31]]);
+            let mut data = chain(first_cluster);
+            data.truncate(size as usize);
+            Entry { name: entry[0..11].try_into().unwrap(), attr: entry[11], first_cluster, size, data }
+        })
+        .collect()
+}
+
+/// Opération appliquée à une image.
+#[derive(Debug, Clone)]
+enum Op {
+    /// Crée le fichier s'il n'existe pas, puis remplace son contenu.
+    Write { name: [u8; 11], data: Vec<u8> },
+    /// Supprime l'un des fichiers présents.
+    Remove(prop::sample::Index),
+}
+
+fn ops(cluster_size: usize) -> impl Strategy<Value = Vec<Op>> {
+    let op = prop_oneof![
+        2 => (short_name(), prop::collection::vec(any::<u8>(), 0..=cluster_size * 3))
+            .prop_map(|(name, data)| Op::Write { name, data }),
+        1 => any::<prop::sample::Index>().prop_map(Op::Remove),
+    ];
+    prop::collection::vec(op, 1..=8)
+}
+
+/// Nom passé à `create_file` pour le nom court `name`.
+fn display_name(name: &[u8; 11]) -> String {
+    DirectoryEntry { name: *name, attr: 0, first_cluster: 0, size: 0, long_name: None }.filename()
+}
+
+/// Vérifie que toutes les copies de la FAT sont identiques et qu'aucun
+/// cluster n'est perdu : chaque cluster occupé appartient à un fichier ou au
+/// répertoire racine.
+fn check_fats(disk: &[u8], entries: &[Entry]) {
+    let u32_at = |at: usize| u32::from_le_bytes(disk[at..at + 4].try_into().unwrap());
+    let fat_start = u16::from_le_bytes([disk[14], disk[15]]) as usize * SECTOR;
+    let fat_len = u32_at(36) as usize * SECTOR;
+    let first = &disk[fat_start..fat_start + fat_len];
+    for copy in 1..disk[16] as usize {
+        let start = fat_start + copy * fat_len;
+        assert_eq!(&disk[start..start + fat_len], first, "FAT copy {} differs", copy);
+    }
+
+    let chain_len = |mut cluster: u32| {
+        let mut len = 0;
+        while (2..0x0FFF_FFF8).contains(&cluster) {
+            len += 1;
+            cluster = u32_at(fat_start + cluster as usize * 4) & 0x0FFF_FFFF;
+        }
+        len
+    };
+    let used = (2..2 + MAX_CLUSTERS).filter(|&cluster| u32_at(fat_start + cluster * 4) & 0x0FFF_FFFF != 0).count();
+    let owned: usize = entries.iter().map(|entry| chain_len(entry.first_cluster)).sum::<usize>() + chain_len(u32_at(44));
+    assert_eq!(used, owned, "clusters leaked");
+}
+
+proptest! {
+    #[test]
+    fn reader_matches_reference_parser(image in image()) {
+        let disk = image.build();
+        let reference = reference_parse(&disk);
+        let expected: Vec<File> = reference
+            .iter()
+            .map(|entry| File { name: entry.name, data: entry.data.clone() })
+            .collect();
+        prop_assert_eq!(&expected, &image.files);
+
+        let mut fs = Fat32::new(VecDisk(disk)).unwrap();
+        prop_assert_eq!(fs.cluster_size(), image.cluster_size());
+        let entries = fs.read_root_directory().unwrap();
+        prop_assert_eq!(entries.len(), reference.len());
+        for (entry, reference) in entries.iter().zip(&reference) {
+            prop_assert_eq!(entry.name, reference.name);
+            prop_assert_eq!(entry.attr, reference.attr);
+            prop_assert_eq!(entry.first_cluster, reference.first_cluster);
+            prop_assert_eq!(entry.size, reference.size);
+            prop_assert_eq!(&fs.open_file(entry).unwrap(), &reference.data);
+        }
+    }
+}
+
+#[test]
+fn memory_disk_reads_hello() {
+    let mut fs = Fat32::new(host_tests::fat32::MemoryDisk::new()).unwrap();
+    let entries = fs.read_root_directory().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].filename(), "HELLO.TXT");
+    assert_eq!(fs.open_file(&entries[0]).unwrap(), b"Hello");
+}
+
+proptest! {
+    #[test]
+    fn writes_and_removals_survive_remount((image, ops) in image().prop_flat_map(|image| {
+        let cluster_size = image.cluster_size();
+        (Just(image), ops(cluster_size))
+    })) {
+        let mut fs = Fat32::new(VecDisk(image.build())).unwrap();
+        let root = fs.boot_sector().root_cluster;
+        let mut model = image.files.clone();
+        for op in &ops {
+            match op {
+                Op::Write { name: short, data } => {
+                    let name = display_name(short);
+                    let mut entry = match fs.read_root_directory().unwrap().into_iter().find(|e| e.name == *short) {
+                        Some(entry) => {
+                            prop_assert!(matches!(fs.create_file(root, &name), Err(FatError::AlreadyExists)));
+                            entry
+                        }
+                        None => {
+                            let entry = fs.create_file(root, &name).unwrap();
+                            model.push(File { name: *short, data: Vec::new() });
+                            entry
+                        }
+                    };
+                    fs.write_file(root, &mut entry, data).unwrap();
+                    prop_assert_eq!(entry.size as usize, data.len());
+                    model.iter_mut().find(|file| file.name == *short).unwrap().data = data.clone();
+                }
+                Op::Remove(index) => {
+                    if model.is_empty() {
+                        continue;
+                    }
+                    let short = model[index.index(model.len())].name;
+                    fs.remove(&format!("/{}", display_name(&short))).unwrap();
+                    // Parmi des homonymes, c'est la première entrée qui est supprimée.
+                    let first = model.iter().position(|file| file.name == short).unwrap();
+                    model.remove(first);
+                }
+            }
+        }
+
+        let disk = fs.into_device().0;
+        let reference = reference_parse(&disk);
+        check_fats(&disk, &reference);
+        let mut fs = Fat32::new(VecDisk(disk)).unwrap();
+        let mut read: Vec<File> = Vec::new();
+        for entry in fs.read_root_directory().unwrap() {
+            let data = fs.open_file(&entry).unwrap();
+            read.push(File { name: entry.name, data });
+        }
+        let mut parsed: Vec<File> = reference
+            .into_iter()
+            .map(|entry| File { name: entry.name, data: entry.data })
+            .collect();
+        let key = |file: &File| (file.name, file.data.clone());
+        model.sort_by_key(key);
+        read.sort_by_key(key);
+        parsed.sort_by_key(key);
+        prop_assert_eq!(&read, &model);
+        prop_assert_eq!(&parsed, &model);
+    }
+}
+
+#[test]
+fn memory_disk_write_and_remount() {
+    let mut fs = Fat32::new(MemoryDisk::new()).unwrap();
+    let root = fs.boot_sector().root_cluster;
+    let data: Vec<u8> = (0..700u32).map(|i| i as u8).collect();
+    let mut entry = fs.create_file(root, "notes.txt").unwrap();
+    assert_eq!(entry.filename(), "NOTES.TXT");
+    fs.write_file(root, &mut entry, &data).unwrap();
+
+    let mut fs = Fat32::new(fs.into_device()).unwrap();
+    let entries = fs.read_root_directory().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[1].filename(), "NOTES.TXT");
+    assert_eq!(entries[1].size, 700);
+    assert_eq!(fs.open_file(&entries[1]).unwrap(), data);
+    assert_eq!(fs.open_file(&entries[0]).unwrap(), b"Hello");
+    // Les deux copies de la FAT ont reçu la même chaîne.
+    for cluster in [4, 5] {
+        assert_ne!(fs.read_fat_entry(cluster), 0);
+    }
+    let mut fat1 = [0u8; 512];
+    let mut fat2 = [0u8; 512];
+    let mut disk = fs.into_device();
+    disk.read_sector(1, &mut fat1);
+    disk.read_sector(2, &mut fat2);
+    assert_eq!(fat1, fat2);
+}
+
+#[test]
+fn write_fails_cleanly_when_disk_is_full() {
+    let mut fs = Fat32::new(MemoryDisk::new()).unwrap();
+    let root = fs.boot_sector().root_cluster;
+    assert_eq!(fs.cluster_count(), 5);
+    let mut entry = fs.create_file(root, "BIG.BIN").unwrap();
+    // Trois clusters libres (4, 5, 6) : quatre ne tiennent pas.
+    assert!(matches!(fs.write_file(root, &mut entry, &[1; 4 * 512]), Err(FatError::DiskFull)));
+    assert_eq!((entry.first_cluster, entry.size), (0, 0));
+    assert_eq!((4..=6).map(|cluster| fs.read_fat_entry(cluster)).collect::<Vec<_>>(), [0, 0, 0]);
+
+    fs.write_file(root, &mut entry, &[1; 3 * 512]).unwrap();
+    // Raccourcir le fichier rend ses clusters.
+    fs.write_file(root, &mut entry, b"x").unwrap();
+    assert_eq!((5..=6).map(|cluster| fs.read_fat_entry(cluster)).collect::<Vec<_>>(), [0, 0]);
+    assert!(matches!(fs.create_file(root, "big.bin"), Err(FatError::AlreadyExists)));
+    assert!(matches!(fs.create_file(root, "what?.txt"), Err(FatError::InvalidName)));
+}
+
+#[test]
+fn full_directory_is_extended() {
+    let mut fs = Fat32::new(MemoryDisk::new()).unwrap();
+    let root = fs.boot_sector().root_cluster;
+    // Le cluster racine (512 o) contient 16 entrées, dont HELLO.TXT.
+    for i in 0..16 {
+        fs.create_file(root, &format!("F{}", i)).unwrap();
+    }
+    assert_ne!(fs.read_fat_entry(root), 0x0FFF_FFFF);
+    let mut fs = Fat32::new(fs.into_device()).unwrap();
+    let entries = fs.read_root_directory().unwrap();
+    assert_eq!(entries.len(), 17);
+    assert_eq!(entries[16].filename(), "F15");
+}
+
+/// Remplace l'entrée `index` du cluster racine de `MemoryDisk` (secteur 3).
+fn patch_root_entry(disk: &mut MemoryDisk, index: usize, raw: &[u8; 32]) {
+    let mut sector = [0u8; 512];
+    disk.read_sector(3, &mut sector);
+    sector[index * 32..index * 32 + 32].copy_from_slice(raw);
+    disk.write_sector(3, &sector);
+}
+
+#[test]
+fn removed_file_space_is_reusable() {
+    let mut fs = Fat32::new(MemoryDisk::new()).unwrap();
+    let root = fs.boot_sector().root_cluster;
+    let mut entry = fs.create_file(root, "BIG.BIN").unwrap();
+    // HELLO.TXT occupe le cluster 3 : quatre clusters ne tiennent pas.
+    assert!(matches!(fs.write_file(root, &mut entry, &[7; 4 * 512]), Err(FatError::DiskFull)));
+
+    fs.remove("/HELLO.TXT").unwrap();
+    assert_eq!(fs.read_fat_entry(3), 0);
+    assert!(matches!(fs.remove("/HELLO.TXT"), Err(FatError::NotFound)));
+    fs.write_file(root, &mut entry, &[7; 4 * 512]).unwrap();
+
+    let mut fs = Fat32::new(fs.into_device()).unwrap();
+    let entries = fs.read_root_directory().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].filename(), "BIG.BIN");
+    assert_eq!(fs.open_file(&entries[0]).unwrap(), [7; 4 * 512]);
+
+    // La suppression libère la chaîne dans les deux copies de la FAT.
+    fs.remove("big.bin").unwrap();
+    let mut disk = fs.into_device();
+    for fat in [1, 2] {
+        let mut sector = [0u8; 512];
+        disk.read_sector(fat, &mut sector);
+        assert!(sector[3 * 4..7 * 4].iter().all(|&b| b == 0), "FAT {} not freed", fat);
+    }
+    let mut fs = Fat32::new(disk).unwrap();
+    assert!(fs.read_root_directory().unwrap().is_empty());
+}
+
+#[test]
+fn remove_marks_long_name_entries() {
+    let mut disk = MemoryDisk::new();
+    let mut long = [0u8; 32];
+    long[0] = 0x41;
+    long[1..11].copy_from_slice(b"h\0e\0l\0l\0o\0");
+    long[11] = 0x0F;
+    let mut sector = [0u8; 512];
+    disk.read_sector(3, &mut sector);
+    let hello: [u8; 32] = sector[..32].try_into().unwrap();
+    // Entrée de nom long, puis l'entrée courte de HELLO.TXT.
+    patch_root_entry(&mut disk, 0, &long);
+    patch_root_entry(&mut disk, 1, &hello);
+
+    let mut fs = Fat32::new(disk).unwrap();
+    fs.remove("HELLO.TXT").unwrap();
+    let mut disk = fs.into_device();
+    let mut sector = [0u8; 512];
+    disk.read_sector(3, &mut sector);
+    assert_eq!((sector[0], sector[32]), (0xE5, 0xE5));
+}
+
+#[test]
+fn remove_refuses_non_empty_directory() {
+    let mut fs = Fat32::new(MemoryDisk::new()).unwrap();
+    let root = fs.boot_sector().root_cluster;
+    // Un répertoire SUB (cluster 4) contenant `.`, `..` puis FILE.
+    let mut sub = fs.create_file(root, "SUB").unwrap();
+    let mut content = [0u8; 96];
+    content[0..11].copy_from_slice(b".          ");
+    content[32..43].copy_from_slice(b"..         ");
+    content[64..75].copy_from_slice(b"FILE       ");
+    for raw in content.chunks_mut(32) {
+        raw[11] = 0x10;
+    }
+    content[64 + 11] = 0x20;
+    fs.write_file(root, &mut sub, &content).unwrap();
+    let mut disk = fs.into_device();
+    let mut sector = [0u8; 512];
+    disk.read_sector(3, &mut sector);
+    let mut raw: [u8; 32] = sector[32..64].try_into().unwrap();
+    raw[11] = 0x10;
+    patch_root_entry(&mut disk, 1, &raw);
+
+    let mut fs = Fat32::new(disk).unwrap();
+    assert!(matches!(fs.remove("/SUB"), Err(FatError::DirectoryNotEmpty)));
+    assert_eq!(fs.read_root_directory().unwrap().len(), 2);
+
+    // Sans FILE, il ne reste que `.` et `..` : la suppression est acceptée.
+    content[64] = 0xE5;
+    let mut disk = fs.into_device();
+    let mut sector = [0u8; 512];
+    sector[..96].copy_from_slice(&content);
+    disk.write_sector(5, &sector);
+    let mut fs = Fat32::new(disk).unwrap();
+    fs.remove("/SUB").unwrap();
+    assert_eq!(fs.read_fat_entry(4), 0);
+}
+
+#[test]
+fn write_file_refuses_directories() {
+    let mut fs = Fat32::new(MemoryDisk::new()).unwrap();
+    let root = fs.boot_sector().root_cluster;
+    fs.create_file(root, "SUB").unwrap();
+    let mut disk = fs.into_device();
+    let mut sector = [0u8; 512];
+    disk.read_sector(3, &mut sector);
+    let mut raw: [u8; 32] = sector[32..64].try_into().unwrap();
+    raw[11] = 0x10;
+    patch_root_entry(&mut disk, 1, &raw);
+
+    let mut fs = Fat32::new(disk).unwrap();
+    let mut sub = fs.lookup("/SUB").unwrap();
+    assert!(matches!(fs.write_file(root, &mut sub, b"data"), Err(FatError::IsADirectory)));
+}
+
+#[test]
+fn files_are_not_directories() {
+    let mut fs = Fat32::new(MemoryDisk::new()).unwrap();
+    let mut hello = fs.read_root_directory().unwrap().remove(0);
+    let file = hello.first_cluster;
+    assert!(matches!(fs.create_file(file, "NEW.TXT"), Err(FatError::NotADirectory)));
+    assert!(matches!(fs.write_file(file, &mut hello, b"data"), Err(FatError::NotADirectory)));
+    assert!(matches!(fs.read_directory(file), Err(FatError::NotADirectory)));
+    assert_eq!(fs.open_file(&hello).unwrap(), b"Hello");
+}
+
+#[test]
+fn cluster_zero_is_the_root_directory() {
+    let mut fs = Fat32::new(MemoryDisk::new()).unwrap();
+    let mut entry = fs.create_file(0, "NEW.TXT").unwrap();
+    fs.write_file(0, &mut entry, b"root").unwrap();
+    assert!(matches!(fs.create_file(0, "new.txt"), Err(FatError::AlreadyExists)));
+    assert_eq!(fs.open_path("/NEW.TXT").unwrap(), b"root");
+    assert_eq!(fs.read_directory(0).unwrap().len(), fs.read_root_directory().unwrap().len());
+}
+
+#[test]
+fn corrupt_chains_are_errors() {
+    let mut fs = Fat32::new(MemoryDisk::new()).unwrap();
+    let hello = fs.read_root_directory().unwrap().remove(0);
+    let outside = DirectoryEntry { first_cluster: 99, ..hello.clone() };
+    assert!(matches!(fs.open_file(&outside), Err(FatError::InvalidCluster)));
+    // HELLO.TXT pointe sur lui-même : la chaîne boucle.
+    fs.write_fat_entry(hello.first_cluster, hello.first_cluster);
+    assert!(matches!(fs.open_file(&hello), Err(FatError::InvalidCluster)));
+}
+
+#[test]
+fn zero_sized_clusters_or_fat_are_rejected() {
+    // Secteurs par cluster (octet 13), puis secteurs par FAT (octets 36..40).
+    for field in [13..14, 36..40] {
+        let mut disk = MemoryDisk::new();
+        let mut sector = [0u8; 512];
+        disk.read_sector(0, &mut sector);
+        sector[field].fill(0);
+        disk.write_sector(0, &sector);
+        assert!(matches!(Fat32::new(disk), Err(FatError::InvalidBootSector)));
+    }
+}
+
+/// Nœud d'une arborescence : fichier ou répertoire (enfants par nom court).
+#[derive(Debug, Clone)]
+enum Node {
+    File(Vec<u8>),
+    Dir(BTreeMap<[u8; 11], Node>),
+}
+
+fn tree() -> impl Strategy<Value = BTreeMap<[u8; 11], Node>> {
+    let file = prop::collection::vec(any::<u8>(), 0..=2 * SECTOR).prop_map(Node::File);
+    let node = file.prop_recursive(3, 24, 4, |inner| {
+        prop::collection::btree_map(short_name(), inner, 0..=4).prop_map(Node::Dir)
+    });
+    prop::collection::btree_map(short_name(), node, 0..=5)
+}
+
+/// Image d'une arborescence, un secteur par cluster, clusters pris dans
+/// l'ordre de construction.
+#[derive(Default)]
+struct TreeImage {
+    clusters: Vec<[u8; SECTOR]>,
+    fat: Vec<u32>,
+}
+
+fn raw_entry(name: &[u8; 11], attr: u8, cluster: u32, size: u32) -> [u8; 32] {
+    let mut raw = [0u8; 32];
+    raw[0..11].copy_from_slice(name);
+    raw[11] = attr;
+    raw[20..22].copy_from_slice(&((cluster >> 16) as u16).to_le_bytes());
+    raw[26..28].copy_from_slice(&(cluster as u16).to_le_bytes());
+    raw[28..32].copy_from_slice(&size.to_le_bytes());
+    raw
+}
+
+impl TreeImage {
+    /// Écrit `data` sur une chaîne d'au moins `min` clusters et retourne son
+    /// premier cluster (0 si la chaîne est vide).
+    fn chain(&mut self, data: &[u8], min: usize) -> u32 {
+        let count = data.len().div_ceil(SECTOR).max(min);
+        let first = self.clusters.len() as u32 + 2;
+        for i in 0..count {
+            let mut cluster = [0u8; SECTOR];
+            let chunk = data.chunks(SECTOR).nth(i).unwrap_or(&[]);
+            cluster[..chunk.len()].copy_from_slice(chunk);
+            self.clusters.push(cluster);
+            self.fat.push(if i + 1 == count { 0x0FFF_FFFF } else { first + i as u32 + 1 });
+        }
+        if count == 0 { 0 } else { first }
+    }
+
+    /// Construit un répertoire et ses descendants. `parent` est le cluster
+    /// à inscrire dans `..`, `None` pour la racine, qui n'a ni `.` ni `..`.
+    fn dir(&mut self, children: &BTreeMap<[u8; 11], Node>, parent: Option<u32>) -> u32 {
+        let cluster = self.chain(&[], 1);
+        let mut raw = Vec::new();
+        if let Some(parent) = parent {
+            raw.extend_from_slice(&raw_entry(b".          ", 0x10, cluster, 0));
+            raw.extend_from_slice(&raw_entry(b"..         ", 0x10, parent, 0));
+        }
+        // `..` des sous-répertoires de la racine vaut 0.
+        let own = if parent.is_some() { cluster } else { 0 };
+        for (name, node) in children {
+            let entry = match node {
+                Node::File(data) => raw_entry(name, 0x20, self.chain(data, 0), data.len() as u32),
+                Node::Dir(children) => raw_entry(name, 0x10, self.dir(children, Some(own)), 0),
+            };
+            raw.extend_from_slice(&entry);
+        }
+        self.clusters[cluster as usize - 2][..raw.len()].copy_from_slice(&raw);
+        cluster
+    }
+
+    fn build(root: &BTreeMap<[u8; 11], Node>) -> Vec<u8> {
+        let mut image = TreeImage::default();
+        let root = image.dir(root, None);
+        let mut fat = vec![0x0FFF_FFF8, 0x0FFF_FFFF];
+        fat.append(&mut image.fat);
+        let sectors_per_fat = (fat.len() * 4).div_ceil(SECTOR);
+        let data_start = (1 + 2 * sectors_per_fat) * SECTOR;
+
+        let mut disk = vec![0u8; data_start + image.clusters.len() * SECTOR];
+        let total_sectors = (disk.len() / SECTOR) as u32;
+        disk[11..13].copy_from_slice(&(SECTOR as u16).to_le_bytes());
+        disk[13] = 1;
+        disk[14..16].copy_from_slice(&1u16.to_le_bytes());
+        disk[16] = 2;
+        disk[32..36].copy_from_slice(&total_sectors.to_le_bytes());
+        disk[36..40].copy_from_slice(&(sectors_per_fat as u32).to_le_bytes());
+        disk[44..48].copy_from_slice(&root.to_le_bytes());
+        let fat: Vec<u8> = fat.iter().flat_map(|entry| entry.to_le_bytes()).collect();
+        for copy in 0..2 {
+            let start = SECTOR + copy * sectors_per_fat * SECTOR;
+            disk[start..start + fat.len()].copy_from_slice(&fat);
+        }
+        disk[data_start..].copy_from_slice(&image.clusters.concat());
+        disk
+    }
+}
+
+/// Parcourt le modèle sous `path` et vérifie chaque chemin avec `lookup`,
+/// `open_path` et `read_directory`. `cluster` est le premier cluster du
+/// répertoire `path`, `parent` celui de son parent.
+fn check_tree(
+    fs: &mut Fat32<VecDisk>,
+    children: &BTreeMap<[u8; 11], Node>,
+    path: &str,
+    cluster: u32,
+    parent: u32,
+) {
+    assert_eq!(fs.lookup(&format!("{}/.", path)).unwrap().first_cluster, cluster);
+    assert_eq!(fs.lookup(&format!("{}/..", path)).unwrap().first_cluster, parent);
+    assert!(matches!(fs.lookup(&format!("{}/NOPE~1", path)), Err(FatError::NotFound)));
+
+    let mut names: Vec<[u8; 11]> = fs
+        .read_directory(cluster)
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.name)
+        .filter(|name| name[0] != b'.')
+        .collect();
+    names.sort();
+    assert_eq!(names, children.keys().copied().collect::<Vec<_>>());
+
+    for (name, node) in children {
+        let child = format!("{}/{}", path, display_name(name));
+        let entry = fs.lookup(&child).unwrap();
+        match node {
+            Node::File(data) => {
+                assert!(!entry.is_directory());
+                assert_eq!(&fs.open_path(&child).unwrap(), data);
+                // Minuscules et composants `.` / `..` redondants.
+                let detour = format!("/..{}/./{}", path, display_name(name)).to_lowercase();
+                assert_eq!(&fs.open_path(&detour).unwrap(), data);
+                assert!(matches!(fs.lookup(&format!("{}/X", child)), Err(FatError::NotADirectory)));
+            }
+            Node::Dir(grandchildren) => {
+                assert!(entry.is_directory());
+                assert!(matches!(fs.open_path(&child), Err(FatError::IsADirectory)));
+                check_tree(fs, grandchildren, &child, entry.first_cluster, cluster);
+            }
+        }
+    }
+}
+
+/// Supprime l'arborescence `children` de `path` en partant des feuilles ;
+/// un répertoire non vide est refusé.
+fn remove_tree(fs: &mut Fat32<VecDisk>, children: &BTreeMap<[u8; 11], Node>, path: &str) {
+    for (name, node) in children {
+        let child = format!("{}/{}", path, display_name(name));
+        if let Node::Dir(grandchildren) = node {
+            if !grandchildren.is_empty() {
+                assert!(matches!(fs.remove(&child), Err(FatError::DirectoryNotEmpty)));
+            }
+            remove_tree(fs, grandchildren, &child);
+        }
+        fs.remove(&child).unwrap();
+        assert!(matches!(fs.lookup(&child), Err(FatError::NotFound)));
+    }
+}
+
+proptest! {
+    #[test]
+    fn paths_resolve_in_random_trees(root in tree()) {
+        let mut fs = Fat32::new(VecDisk(TreeImage::build(&root))).unwrap();
+        let root_cluster = fs.boot_sector().root_cluster;
+        prop_assert_eq!(fs.lookup("/").unwrap().first_cluster, root_cluster);
+        check_tree(&mut fs, &root, "", root_cluster, root_cluster);
+
+        remove_tree(&mut fs, &root, "");
+        prop_assert!(fs.read_root_directory().unwrap().is_empty());
+        for cluster in (2..fs.cluster_count() + 2).filter(|&cluster| cluster != root_cluster) {
+            prop_assert_eq!(fs.read_fat_entry(cluster), 0);
+        }
+    }
+}
+
+#[test]
+fn open_path_errors() {
+    let mut fs = Fat32::new(MemoryDisk::new()).unwrap();
+    assert_eq!(fs.open_path("/HELLO.TXT").unwrap(), b"Hello");
+    assert_eq!(fs.open_path("/../hello.txt").unwrap(), b"Hello");
+    assert!(matches!(fs.open_path("/BOOT/KERNEL.CFG"), Err(FatError::NotFound)));
+    assert!(matches!(fs.open_path("/HELLO.TXT/KERNEL.CFG"), Err(FatError::NotADirectory)));
+    assert!(matches!(fs.open_path("/HELLO.TXT/.."), Err(FatError::NotADirectory)));
+    assert!(matches!(fs.open_path("/"), Err(FatError::IsADirectory)));
+    assert!(matches!(fs.open_path("/not a valid 8.3 name"), Err(FatError::NotFound)));
+}
+
+/// Somme de contrôle d'un nom court, telle que la spécifie Microsoft.
+fn reference_checksum(name: &[u8; 11]) -> u8 {
+    let mut sum: u8 = 0;
+    for &b in name {
+        sum = (if sum & 1 != 0 { 0x80u8 } else { 0 }).wrapping_add(sum >> 1).wrapping_add(b);
+    }
+    sum
+}
+
+/// Entrées de nom long de `long` pour le nom court `short`, dans l'ordre
+/// du disque (dernière partie du nom en premier).
+fn lfn_entries(long: &str, short: &[u8; 11]) -> Vec<[u8; 32]> {
+    let mut units: Vec<u16> = long.encode_utf16().collect();
+    if !units.len().is_multiple_of(13) {
+        units.push(0);
+        units.resize(units.len().div_ceil(13) * 13, 0xFFFF);
+    }
+    let count = units.len() / 13;
+    let mut entries: Vec<[u8; 32]> = units
+        .chunks(13)
+        .enumerate()
+        .map(|(i, chars)| {
+            let mut raw = [0u8; 32];
+            raw[0] = (i as u8 + 1) | if i + 1 == count { 0x40 } else { 0 };
+            raw[11] = 0x0F;
+            raw[13] = reference_checksum(short);
+            let offsets = (1..11).step_by(2).chain((14..26).step_by(2)).chain((28..32).step_by(2));
+            for (at, unit) in offsets.zip(chars) {
+                raw[at..at + 2].copy_from_slice(&unit.to_le_bytes());
+            }
+            raw
+        })
+        .collect();
+    entries.reverse();
+    entries
+}
+
+/// Nom long valide : pas d'espace ni de point en tête ou en fin.
+fn long_name() -> impl Strategy<Value = String> {
+    "[a-zA-Z0-9_é日本-]([a-zA-Z0-9 ._é日本-]{0,24}[a-zA-Z0-9_é日本-])?"
+}
+
+/// Remplace les entrées du cluster racine (cluster 2) d'une image construite
+/// par `TreeImage` : chaque entrée courte est précédée des entrées de nom long
+/// de `longs`.
+fn insert_long_names(disk: &mut [u8], longs: &[String]) {
+    let root = (1 + 2 * u32::from_le_bytes(disk[36..40].try_into().unwrap()) as usize) * SECTOR;
+    let shorts: Vec<[u8; 32]> = disk[root..root + SECTOR].chunks(32).take(longs.len()).map(|raw| raw.try_into().unwrap()).collect();
+    let mut raw = Vec::new();
+    for (short, long) in shorts.iter().zip(longs) {
+        raw.extend(lfn_entries(long, short[0..11].try_into().unwrap()));
+        raw.push(*short);
+    }
+    let raw = raw.concat();
+    disk[root..root + raw.len()].copy_from_slice(&raw);
+}
+
+proptest! {
+    #[test]
+    fn long_names_are_decoded_and_looked_up(
+        files in prop::collection::vec((long_name(), prop::collection::vec(any::<u8>(), 0..=SECTOR)), 1..=5)
+    ) {
+        // Noms longs distincts sans tenir compte de la casse, noms courts F0, F1…
+        // qu'aucun nom long ne reprend.
+        let mut seen = std::collections::HashSet::new();
+        let is_short = |long: &str| long.len() == 2 && long.starts_with(['f', 'F']) && long.as_bytes()[1].is_ascii_digit();
+        let files: Vec<(String, Vec<u8>)> =
+            files.into_iter().filter(|(long, _)| !is_short(long) && seen.insert(long.to_lowercase())).collect();
+        prop_assume!(!files.is_empty());
+        let root: BTreeMap<[u8; 11], Node> = files
+            .iter()
+            .enumerate()
+            .map(|(i, (_, data))| {
+                let mut short = *b"           ";
+                short[0] = b'F';
+                short[1] = b'0' + i as u8;
+                (short, Node::File(data.clone()))
+            })
+            .collect();
+        let longs: Vec<String> = files.iter().map(|(long, _)| long.clone()).collect();
+        let mut disk = TreeImage::build(&root);
+        // Au plus 2 entrées de nom long par fichier : 5 × 3 entrées tiennent dans le cluster.
+        insert_long_names(&mut disk, &longs);
+
+        let mut fs = Fat32::new(VecDisk(disk)).unwrap();
+        let entries = fs.read_root_directory().unwrap();
+        prop_assert_eq!(entries.len(), files.len());
+        for (i, (entry, (long, data))) in entries.iter().zip(&files).enumerate() {
+            prop_assert_eq!(entry.long_name.as_deref(), Some(long.as_str()));
+            prop_assert_eq!(entry.filename(), format!("F{}", i));
+            prop_assert_eq!(&fs.open_path(&format!("/{}", long)).unwrap(), data);
+            prop_assert_eq!(&fs.open_path(&format!("/{}", long.to_uppercase())).unwrap(), data);
+            prop_assert_eq!(&fs.open_path(&format!("/f{}", i)).unwrap(), data);
+        }
+
+        // La suppression par nom long marque aussi les entrées de nom long.
+        let path = format!("/{}", longs[0]);
+        fs.remove(&path).unwrap();
+        prop_assert!(matches!(fs.lookup(&path), Err(FatError::NotFound)));
+        prop_assert_eq!(fs.read_root_directory().unwrap().len(), files.len() - 1);
+    }
+}
+
+/// `MemoryDisk` dont l'entrée HELLO.TXT est précédée de `entries`.
+fn hello_with(entries: &[[u8; 32]]) -> Fat32<MemoryDisk> {
+    let mut disk = MemoryDisk::new();
+    let mut sector = [0u8; 512];
+    disk.read_sector(3, &mut sector);
+    let hello: [u8; 32] = sector[..32].try_into().unwrap();
+    for (i, raw) in entries.iter().chain(Some(&hello)).enumerate() {
+        patch_root_entry(&mut disk, i, raw);
+    }
+    Fat32::new(disk).unwrap()
+}
+
+#[test]
+fn long_name_sequence_is_validated() {
+    let long = "Hello, world of long names.txt";
+    let valid = lfn_entries(long, b"HELLO   TXT");
+    assert_eq!(valid.len(), 3);
+    let mut fs = hello_with(&valid);
+    let entries = fs.read_root_directory().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].long_name.as_deref(), Some(long));
+    assert_eq!(fs.open_path("/hello, WORLD of long names.txt").unwrap(), b"Hello");
+
+    // Somme de contrôle d'un autre nom court : le nom long est ignoré.
+    let mut fs = hello_with(&lfn_entries(long, b"OTHER   TXT"));
+    let entries = fs.read_root_directory().unwrap();
+    assert_eq!((entries.len(), entries[0].long_name.as_ref()), (1, None));
+    assert!(matches!(fs.open_path(&format!("/{}", long)), Err(FatError::NotFound)));
+    assert_eq!(fs.open_path("/HELLO.TXT").unwrap(), b"Hello");
+
+    // Séquence incomplète (entrée n° 2 absente) ou désordonnée.
+    for entries in [vec![valid[0], valid[2]], vec![valid[1], valid[0], valid[2]]] {
+        let mut fs = hello_with(&entries);
+        let entries = fs.read_root_directory().unwrap();
+        assert_eq!((entries.len(), entries[0].long_name.as_ref()), (1, None));
+    }
+
+    // Une entrée supprimée interrompt la séquence.
+    let mut deleted = valid.clone();
+    deleted[1][0] = 0xE5;
+    let mut fs = hello_with(&deleted);
+    assert_eq!(fs.read_root_directory().unwrap()[0].long_name, None);
+
+    // Les bits réservés 6 et 7 de l'attribut sont ignorés.
+    let mut reserved = valid.clone();
+    for raw in &mut reserved {
+        raw[11] |= 0xC0;
+    }
+    let mut fs = hello_with(&reserved);
+    let entries = fs.read_root_directory().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].long_name.as_deref(), Some(long));
+    fs.remove(&format!("/{}", long)).unwrap();
+    assert!(fs.read_root_directory().unwrap().is_empty());
+}
+
+proptest! {
+    #[test]
+    fn created_long_names_survive_remount(
+        image in image(),
+        files in prop::collection::vec((long_name(), prop::collection::vec(any::<u8>(), 0..=SECTOR)), 1..=8),
+    ) {
+        let mut fs = Fat32::new(VecDisk(image.build())).unwrap();
+        let root = fs.boot_sector().root_cluster;
+        let mut created: Vec<(String, Vec<u8>)> = Vec::new();
+        for (long, data) in files {
+            let exists = image.files.iter().any(|file| display_name(&file.name).eq_ignore_ascii_case(&long))
+                || created.iter().any(|(other, _)| other.to_lowercase() == long.to_lowercase());
+            match fs.create_file(root, &long) {
+                Err(FatError::AlreadyExists) => prop_assert!(exists, "{:?} wrongly exists", long),
+                Ok(mut entry) => {
+                    prop_assert!(!exists, "{:?} created twice", long);
+                    fs.write_file(root, &mut entry, &data).unwrap();
+                    created.push((long, data));
+                }
+                Err(error) => panic!("create_file({:?}): {:?}", long, error),
+            }
+        }
+
+        let disk = fs.into_device().0;
+        check_fats(&disk, &reference_parse(&disk));
+        let mut fs = Fat32::new(VecDisk(disk)).unwrap();
+        let entries = fs.read_root_directory().unwrap();
+        prop_assert_eq!(entries.len(), image.files.len() + created.len());
+        for (long, data) in &created {
+            let path = format!("/{}", long);
+            let entry = fs.lookup(&path).unwrap();
+            // Le nom court, alias compris, n'est porté par aucune autre entrée
+            // (l'image peut en revanche contenir des homonymes entre eux).
+            prop_assert_eq!(entries.iter().filter(|other| other.name == entry.name).count(), 1);
+            // Sans nom long, le nom était un nom 8.3 valide, enregistré tel quel.
+            match &entry.long_name {
+                Some(name) => {
+                    prop_assert_eq!(name, long);
+                    prop_assert!(entry.filename().contains('~'), "alias {:?}", entry.filename());
+                }
+                None => prop_assert!(entry.filename().eq_ignore_ascii_case(long)),
+            }
+            prop_assert_eq!(&fs.open_path(&path).unwrap(), data);
+            prop_assert_eq!(&fs.open_path(&format!("/{}", entry.filename())).unwrap(), data);
+        }
+    }
+}
+
+#[test]
+fn long_names_get_numbered_aliases() {
+    let mut fs = Fat32::new(MemoryDisk::new()).unwrap();
+    let root = fs.boot_sector().root_cluster;
+    let first = fs.create_file(root, "kernel-config.toml").unwrap();
+    assert_eq!(first.filename(), "KERNEL~1.TOM");
+    assert_eq!(first.long_name.as_deref(), Some("kernel-config.toml"));
+    let second = fs.create_file(root, "kernel-config.tomato").unwrap();
+    assert_eq!(second.filename(), "KERNEL~2.TOM");
+    assert_eq!(fs.create_file(root, ".hidden file").unwrap().filename(), "HIDDEN~1");
+    assert_eq!(fs.create_file(root, "état.données.txt").unwrap().filename(), "_TATDO~1.TXT");
+    assert!(matches!(fs.create_file(root, "KERNEL-CONFIG.TOML"), Err(FatError::AlreadyExists)));
+    assert!(matches!(fs.create_file(root, "trailing dot."), Err(FatError::InvalidName)));
+
+    // Entrée courte précédée de ses entrées de nom long, dans l'ordre du disque.
+    let mut disk = fs.into_device();
+    let mut sector = [0u8; 512];
+    disk.read_sector(3, &mut sector);
+    let expected = lfn_entries("kernel-config.toml", b"KERNEL~1TOM");
+    assert_eq!(expected.len(), 2);
+    assert_eq!(&sector[32..64], &expected[0][..]);
+    assert_eq!(&sector[64..96], &expected[1][..]);
+    assert_eq!(&sector[96..107], b"KERNEL~1TOM");
+}
+
+#[test]
+fn long_name_entries_span_directory_clusters() {
+    let mut fs = Fat32::new(MemoryDisk::new()).unwrap();
+    let root = fs.boot_sector().root_cluster;
+    // 27 caractères : 3 entrées de nom long et l'entrée courte. Le cluster
+    // racine (16 entrées, dont HELLO.TXT) en accueille trois, la quatrième
+    // commence dans le cluster ajouté au répertoire.
+    let names: Vec<String> = (0..4).map(|i| format!("long file name number {}.txt", i)).collect();
+    for name in &names {
+        fs.create_file(root, name).unwrap();
+    }
+    let extension = fs.read_fat_entry(root);
+    assert_ne!(extension, 0x0FFF_FFFF);
+
+    let mut fs = Fat32::new(fs.into_device()).unwrap();
+    let entries = fs.read_root_directory().unwrap();
+    assert_eq!(entries.len(), 5);
+    for (entry, name) in entries[1..].iter().zip(&names) {
+        assert_eq!(entry.long_name.as_ref(), Some(name));
+        assert!(fs.open_path(&format!("/{}", name)).unwrap().is_empty());
+    }
+    fs.remove(&format!("/{}", names[3])).unwrap();
+    assert_eq!(fs.read_root_directory().unwrap().len(), 4);
+    // Les entrées libérées à cheval sur les deux clusters sont réutilisées.
+    fs.create_file(root, &names[3]).unwrap();
+    assert_eq!(fs.read_fat_entry(extension), 0x0FFF_FFFF);
+    assert_eq!(fs.lookup(&format!("/{}", names[3])).unwrap().long_name.as_ref(), Some(&names[3]));
+}
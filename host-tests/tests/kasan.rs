@@ -0,0 +1,87 @@
+//! Mémoire fantôme (`kasan`) : les accès hors des octets demandés, dans un
+//! bloc libéré ou dans le heap jamais distribué sont refusés.
+#![cfg(feature = "kasan")]
+
+use core::alloc::{GlobalAlloc, Layout};
+
+use host_tests::allocator::{SimpleAllocator, ViolationKind, PAGE_SIZE};
+
+const HEAP_SIZE: usize = 256 * 1024;
+
+/// Allocateur sur un heap de `HEAP_SIZE` octets, décalé d'un octet.
+fn with_allocator(test: impl FnOnce(&SimpleAllocator)) {
+    let layout = Layout::from_size_align(HEAP_SIZE + PAGE_SIZE, PAGE_SIZE).unwrap();
+    let base = unsafe { std::alloc::alloc(layout) };
+    assert!(!base.is_null());
+    let allocator = SimpleAllocator::new();
+    unsafe { allocator.init(base as usize + 1, HEAP_SIZE) };
+    test(&allocator);
+    unsafe { std::alloc::dealloc(base, layout) };
+}
+
+#[test]
+fn slab_block_has_a_red_zone() {
+    with_allocator(|allocator| {
+        let layout = Layout::from_size_align(20, 4).unwrap();
+        let ptr = unsafe { allocator.alloc(layout) };
+        assert!(allocator.check_write(ptr, 20).is_ok());
+        let violation = allocator.check_read(unsafe { ptr.add(12) }, 12).unwrap_err();
+        assert_eq!(violation.kind, ViolationKind::OutOfBounds);
+        assert_eq!(violation.bad_addr, ptr as usize + 20);
+        assert!(allocator.check_read(unsafe { ptr.add(31) }, 1).is_err());
+        unsafe { allocator.dealloc(ptr, layout) };
+    });
+}
+
+#[test]
+fn freed_block_is_reported_until_reused() {
+    with_allocator(|allocator| {
+        let layout = Layout::from_size_align(48, 8).unwrap();
+        let ptr = unsafe { allocator.alloc(layout) };
+        unsafe { allocator.dealloc(ptr, layout) };
+        let violation = allocator.check_write(ptr, 8).unwrap_err();
+        assert_eq!(violation.kind, ViolationKind::UseAfterFree);
+        assert_eq!(violation.bad_addr, ptr as usize);
+
+        let again = unsafe { allocator.alloc(layout) };
+        assert_eq!(again, ptr);
+        assert!(allocator.check_write(again, 48).is_ok());
+        unsafe { allocator.dealloc(again, layout) };
+    });
+}
+
+#[test]
+fn large_blocks_follow_realloc() {
+    with_allocator(|allocator| {
+        let layout = Layout::from_size_align(1000, 8).unwrap();
+        let ptr = unsafe { allocator.alloc(layout) };
+        assert!(allocator.check_read(ptr, 1000).is_ok());
+        assert!(allocator.check_read(ptr, 1001).is_err());
+
+        // Réduction sur place : la fin du bloc devient inaccessible.
+        let ptr = unsafe { allocator.realloc(ptr, layout, 100) };
+        assert!(allocator.check_read(ptr, 100).is_ok());
+        assert!(allocator.check_read(unsafe { ptr.add(100) }, 1).is_err());
+
+        // Passage dans un slab : l'ancien bloc est libéré.
+        let small = unsafe { allocator.realloc(ptr, Layout::from_size_align(100, 8).unwrap(), 24) };
+        assert_ne!(small, ptr);
+        assert!(allocator.check_read(small, 24).is_ok());
+        assert_eq!(allocator.check_read(ptr, 1).unwrap_err().kind, ViolationKind::UseAfterFree);
+        unsafe { allocator.dealloc(small, Layout::from_size_align(24, 8).unwrap()) };
+    });
+}
+
+#[test]
+fn untouched_heap_and_foreign_memory() {
+    with_allocator(|allocator| {
+        let layout = Layout::from_size_align(64, 8).unwrap();
+        let ptr = unsafe { allocator.alloc(layout) };
+        let end = allocator.stats().large.heap_size;
+        let far = unsafe { ptr.add(end / 2) };
+        assert_eq!(allocator.check_read(far, 1).unwrap_err().kind, ViolationKind::Unallocated);
+        let local = [0u8; 16];
+        assert!(allocator.check_read(local.as_ptr(), local.len()).is_ok());
+        unsafe { allocator.dealloc(ptr, layout) };
+    });
+}
@@ -0,0 +1,121 @@
+//! Mode durci (`slab_debug`) : chaque détecteur doit arrêter le noyau par un
+//! panic explicite.
+#![cfg(feature = "slab_debug")]
+
+use core::alloc::{GlobalAlloc, Layout};
+
+use host_tests::allocator::{SimpleAllocator, PAGE_SIZE};
+
+const HEAP_SIZE: usize = 256 * 1024;
+
+/// Allocateur sur un heap de `HEAP_SIZE` octets. Le heap n'est pas rendu :
+/// le test se termine par un panic, verrou du slab tenu.
+fn with_allocator(test: impl FnOnce(&SimpleAllocator)) {
+    let layout = Layout::from_size_align(HEAP_SIZE, PAGE_SIZE).unwrap();
+    let base = unsafe { std::alloc::alloc(layout) };
+    assert!(!base.is_null());
+    let allocator = SimpleAllocator::new();
+    unsafe { allocator.init(base as usize, HEAP_SIZE) };
+    test(&allocator);
+}
+
+#[test]
+#[should_panic(expected = "double free")]
+fn double_free_panics() {
+    with_allocator(|allocator| {
+        let layout = Layout::from_size_align(24, 8).unwrap();
+        unsafe {
+            let ptr = allocator.alloc(layout);
+            allocator.dealloc(ptr, layout);
+            allocator.dealloc(ptr, layout);
+        }
+    });
+}
+
+#[test]
+#[should_panic(expected = "red zone")]
+fn red_zone_overwrite_panics() {
+    with_allocator(|allocator| {
+        // 20 octets dans un bloc de 32 : les octets 20..32 forment la zone rouge.
+        let layout = Layout::from_size_align(20, 4).unwrap();
+        unsafe {
+            let ptr = allocator.alloc(layout);
+            ptr.add(20).write(0);
+            allocator.dealloc(ptr, layout);
+        }
+    });
+}
+
+#[test]
+#[should_panic(expected = "red zone")]
+fn red_zone_is_checked_on_realloc() {
+    with_allocator(|allocator| {
+        let layout = Layout::from_size_align(20, 4).unwrap();
+        unsafe {
+            let ptr = allocator.alloc(layout);
+            ptr.add(31).write(0);
+            allocator.realloc(ptr, layout, 16);
+        }
+    });
+}
+
+#[test]
+#[should_panic(expected = "not a block boundary")]
+fn interior_pointer_free_panics() {
+    with_allocator(|allocator| {
+        let layout = Layout::from_size_align(24, 8).unwrap();
+        unsafe {
+            let ptr = allocator.alloc(layout);
+            allocator.dealloc(ptr.add(8), layout);
+        }
+    });
+}
+
+#[test]
+#[should_panic(expected = "slab page")]
+fn free_with_the_wrong_class_panics() {
+    with_allocator(|allocator| {
+        let small = Layout::from_size_align(24, 8).unwrap();
+        let large = Layout::from_size_align(100, 8).unwrap();
+        unsafe {
+            let ptr = allocator.alloc(small);
+            allocator.dealloc(ptr, large);
+        }
+    });
+}
+
+#[test]
+#[should_panic(expected = "use after free")]
+fn write_after_free_panics() {
+    with_allocator(|allocator| {
+        let layout = Layout::from_size_align(48, 8).unwrap();
+        unsafe {
+            let ptr = allocator.alloc(layout);
+            allocator.dealloc(ptr, layout);
+            // Après le lien de la liste libre : seul le poison est touché.
+            ptr.add(16).write(0);
+            allocator.alloc(layout);
+        }
+    });
+}
+
+#[test]
+#[should_panic(expected = "slab page")]
+fn free_into_a_released_page_panics() {
+    with_allocator(|allocator| {
+        let layout = Layout::from_size_align(24, 8).unwrap();
+        unsafe {
+            // Remplit la première page, puis entame une seconde.
+            let mut last = allocator.alloc(layout);
+            while allocator.stats().slabs[1].pages < 2 {
+                last = allocator.alloc(layout);
+            }
+            // Seul bloc de sa page : elle retourne à l'allocateur de gros objets.
+            allocator.dealloc(last, layout);
+            #[cfg(feature = "magazines")]
+            allocator.drain_magazines();
+            assert_eq!(allocator.stats().slabs[1].pages, 1);
+            allocator.dealloc(last, layout);
+        }
+    });
+}
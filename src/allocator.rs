@@ -3,107 +3,817 @@
 //! Fournit un allocateur global basé sur plusieurs "slabs" de tailles fixes,
 //! optimisé pour l'allocation fréquente de petits objets. Chaque slab gère
 //! des blocs de même taille, améliorant la rapidité et réduisant la fragmentation.
+//! Les slabs grandissent page par page à partir d'une `PageSource` et rendent
+//! les pages redevenues libres ; les requêtes qui ne tiennent dans aucun slab
+//! sont servies par un allocateur à liste chaînée partageant le même heap.
+//! Des caches d'objets typés (`ObjectCache`) reposent sur les mêmes slabs.
+//! Les classes de taille sont choisies à la construction de `SlabAllocator`
+//! (`SimpleAllocator` pour les classes par défaut, `DEFAULT_CLASSES`).
+//! Avec la feature `buddy`, un allocateur buddy (`BuddyAllocator`) est
+//! disponible comme allocateur global de remplacement ; avec `magazines`,
+//! chaque CPU garde un cache local de blocs par classe (module `magazine`).
+//! Tous les verrous sont pris interruptions masquées (module `irq`) : un
+//! gestionnaire d'interruption peut allouer.
 
 extern crate alloc;
 
+#[cfg(feature = "buddy")]
+mod buddy;
+mod cache;
+mod cpu;
+#[cfg(feature = "slab_debug")]
+mod debug;
+#[cfg(feature = "fault_injection")]
+mod fault;
+#[cfg(any(feature = "alloc_trace", feature = "kasan"))]
+mod frames;
+mod irq;
+#[cfg(feature = "kasan")]
+mod kasan;
+mod linked_list;
+#[cfg(feature = "magazines")]
+mod magazine;
+mod shrink;
+#[cfg(feature = "alloc_trace")]
+mod trace;
+
 use core::alloc::{GlobalAlloc, Layout};
-use core::ptr::null_mut;
-use spin::Mutex;
+use core::fmt;
+use core::mem::size_of;
+use core::ptr::{null_mut, NonNull};
+use spin::Once;
+
+use self::irq::IrqMutex;
+use self::linked_list::LinkedListAllocator;
+#[cfg(feature = "buddy")]
+pub use self::buddy::{BuddyAllocator, BuddyStats};
+pub use self::cache::{CacheBox, ObjectCache};
+pub use self::cpu::init_cpu;
+#[cfg(feature = "magazines")]
+pub use self::cpu::MAX_CPUS;
+#[cfg(feature = "fault_injection")]
+pub use self::fault::Fault;
+#[cfg(feature = "kasan")]
+pub use self::kasan::{Access, Violation, ViolationKind, KASAN_RECORDS};
+pub use self::linked_list::LargeStats;
+#[cfg(feature = "magazines")]
+pub use self::magazine::MAGAZINE_SIZE;
+pub use self::shrink::{RegistryFull, Shrinker, ShrinkerStats, MAX_SHRINKERS};
+#[cfg(feature = "alloc_trace")]
+pub use self::trace::{TRACE_DEPTH, TRACE_LEN};
+
+/// Taille du heap statique de secours par défaut (64 KiB), feature `static_heap`.
+#[cfg(feature = "static_heap")]
+const HEAP_SIZE: usize = 64 * 1024;
+/// Zone de mémoire statique servant de heap lorsque `init` n'a pas été appelé
+/// et qu'aucune autre n'a été fournie par `with_static_heap`.
+#[cfg(feature = "static_heap")]
+static mut HEAP: StaticHeap = StaticHeap([0; HEAP_SIZE]);
+
+/// Heap statique aligné sur une page, pour pouvoir y découper des pages de slab.
+#[cfg(feature = "static_heap")]
+#[repr(align(4096))]
+struct StaticHeap([u8; HEAP_SIZE]);
+
+/// Taille d'une page ; alignement maximal garanti par l'allocateur.
+pub const PAGE_SIZE: usize = 4096;
 
-/// Taille totale du heap alloué pour le slab allocator (16 KiB).
-const HEAP_SIZE: usize = 16 * 1024;
-/// Zone de mémoire statique servant de heap (non initialisée). 
-static mut HEAP: [u8; HEAP_SIZE] = [0; HEAP_SIZE];
+/// Classes de taille de `SimpleAllocator`.
+pub const DEFAULT_CLASSES: [SizeClass; 4] =
+    [SizeClass::new(16), SizeClass::new(32), SizeClass::new(64), SizeClass::new(128)];
+/// Nombre de classes de `SimpleAllocator`.
+pub const N_SLABS: usize = DEFAULT_CLASSES.len();
+/// Plus petite taille de bloc : un bloc libre doit contenir un pointeur.
+const MIN_CLASS_SIZE: usize = size_of::<*mut u8>();
 
-/// Tailles des blocs gérés par chaque slab (doivent être en ordre croissant).
-const SLAB_SIZES: [usize; 4] = [16, 32, 64, 128];
-const N_SLABS: usize = SLAB_SIZES.len();
+/// Nombre maximal de caractères d'une ligne de `SlabAllocator::write_map` ;
+/// au-delà, un caractère représente un groupe de blocs.
+const MAP_WIDTH: usize = 64;
+/// Nombre maximal de blocs par page (plus petite classe possible).
+const MAX_PAGE_BLOCKS: usize = PAGE_SIZE / MIN_CLASS_SIZE;
 
-/// Calcule combien de blocs de taille `obj_size` peuvent tenir dans une portion de heap.
-const fn slab_count(obj_size: usize) -> usize {
-    HEAP_SIZE / (N_SLABS * obj_size)
+/// Octets de garde réservés après chaque objet de slab (feature `slab_debug`).
+#[cfg(feature = "slab_debug")]
+const REDZONE: usize = debug::REDZONE;
+#[cfg(not(feature = "slab_debug"))]
+const REDZONE: usize = 0;
+
+/// Arrondit `addr` au multiple supérieur de `align` (puissance de deux).
+const fn align_up(addr: usize, align: usize) -> usize {
+    (addr + align - 1) & !(align - 1)
+}
+
+/// Classe de taille d'un `SlabAllocator` : un slab de blocs de `size` octets.
+///
+/// ```ignore
+/// static ALLOCATOR: SlabAllocator<3> = SlabAllocator::with_classes([
+///     SizeClass::new(24),
+///     SizeClass::new(48).with_max_pages(16),
+///     SizeClass::new(96),
+/// ]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeClass {
+    /// Taille des blocs, multiple de 8 (pas forcément une puissance de deux).
+    pub size: usize,
+    /// Nombre maximal de pages du slab ; au-delà, les allocations de la
+    /// classe échouent (`AllocError::Exhausted`).
+    pub max_pages: usize,
+}
+
+impl SizeClass {
+    /// Classe de blocs de `size` octets, sans limite de pages.
+    pub const fn new(size: usize) -> Self {
+        SizeClass { size, max_pages: usize::MAX }
+    }
+
+    /// Limite le slab de la classe à `max_pages` pages.
+    pub const fn with_max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = max_pages;
+        self
+    }
+
+    /// Alignement garanti des blocs : la plus grande puissance de deux
+    /// divisant `size`.
+    ///
+    /// Les pages de slab étant alignées sur `PAGE_SIZE` et leurs blocs placés
+    /// à des multiples de `size` après un en-tête aligné de même, c'est aussi
+    /// l'alignement de chaque bloc (`size` pour une puissance de deux).
+    pub const fn align(&self) -> usize {
+        1 << self.size.trailing_zeros()
+    }
+}
+
+/// Fournisseur de pages pour les slabs.
+///
+/// Chaque page fait `PAGE_SIZE` octets et est alignée sur `PAGE_SIZE`. Par
+/// défaut, `SimpleAllocator` prend ses pages dans son allocateur de gros
+/// objets ; un allocateur de frames peut être branché avec `set_page_source`.
+pub trait PageSource {
+    /// Fournit une page libre, ou `None` si aucune n'est disponible.
+    fn alloc_page(&self) -> Option<Page>;
+
+    /// Rend une page obtenue par `alloc_page`.
+    ///
+    /// # Safety
+    /// `page` doit provenir de `alloc_page` sur cette même source et ne plus
+    /// être utilisée.
+    unsafe fn dealloc_page(&self, page: *mut u8);
+}
+
+/// Page fournie par une `PageSource`.
+#[derive(Debug, Clone, Copy)]
+pub struct Page {
+    /// Adresse de la page.
+    pub addr: *mut u8,
+    /// `true` si la page est garantie remplie de zéros : les blocs qui n'en
+    /// ont jamais été retirés peuvent alors être servis par `alloc_zeroed`
+    /// sans être remis à zéro.
+    pub zeroed: bool,
+}
+
+/// En-tête placé au début de chaque page d'un slab.
+#[repr(C)]
+struct SlabPage {
+    /// Page précédente dans la liste (partielle ou pleine) du slab.
+    prev: *mut SlabPage,
+    /// Page suivante dans la même liste.
+    next: *mut SlabPage,
+    /// Premier bloc libéré réutilisable de la page (`null` si aucun).
+    free_list: *mut u8,
+    /// Nombre de blocs jamais distribués depuis l'arrivée de la page : les
+    /// blocs d'indice `bump..capacity` ne sont pas encore chaînés.
+    bump: usize,
+    /// Nombre de blocs alloués dans la page.
+    in_use: usize,
+    /// Page reçue remplie de zéros (voir `Page::zeroed`).
+    zeroed: bool,
+    /// Numéro magique et bitmap des blocs alloués.
+    #[cfg(feature = "slab_debug")]
+    guard: debug::PageGuard,
+}
+
+/// Occupation d'une page de slab, relevée par `Slab::occupancy`.
+struct Occupancy {
+    /// `true` pour chaque bloc libre (jamais distribué ou dans la liste libre).
+    free: [bool; MAX_PAGE_BLOCKS],
+    /// Longueur de la liste libre de la page.
+    free_list: usize,
+    /// Plus longue suite de blocs libres consécutifs.
+    longest_run: usize,
+}
+
+/// Parcourt une liste de pages de slab (`partial` ou `full`).
+///
+/// # Safety
+/// `first` doit être la tête d'une liste valide, non modifiée pendant le parcours.
+unsafe fn page_list(first: *mut SlabPage) -> impl Iterator<Item = *mut SlabPage> {
+    let next = |&page: &*mut SlabPage| {
+        let next = (*page).next;
+        (!next.is_null()).then_some(next)
+    };
+    core::iter::successors((!first.is_null()).then_some(first), next)
 }
 
 /// Structure représentant un cache de blocs de taille fixée (slab).
 ///
-/// Chaque slab maintient une liste libre simple (singly linked list) des blocs disponibles.
+/// Le slab est fait de pages obtenues à la demande. Chaque page distribue
+/// d'abord ses blocs neufs dans l'ordre, puis ceux qui lui ont été rendus via
+/// une liste libre simple (singly linked list) ; les pages ayant encore des
+/// blocs libres sont chaînées dans `partial`, les autres dans `full`.
 struct Slab {
-    /// Pointeur vers le premier bloc libre.
-    free_list: Option<*mut u8>,
+    /// Pages ayant au moins un bloc libre.
+    partial: *mut SlabPage,
+    /// Pages dont tous les blocs sont alloués.
+    full: *mut SlabPage,
     /// Taille de chaque bloc géré par ce slab.
     obj_size: usize,
-    /// Nombre total de blocs initialisés dans ce slab.
-    count: usize,
+    /// Décalage du premier bloc dans une page (après l'en-tête).
+    first_block: usize,
+    /// Nombre de blocs par page.
+    capacity: usize,
+    /// Nombre de pages détenues par ce slab.
+    pages: usize,
+    /// Nombre maximal de pages (`SizeClass::max_pages`).
+    max_pages: usize,
+    /// Nombre de blocs actuellement alloués.
+    in_use: usize,
+    /// Maximum atteint par `in_use`.
+    peak: usize,
+    /// Nombre d'allocations refusées faute de page disponible.
+    failed: usize,
+    /// Somme des tailles demandées pour les blocs alloués.
+    requested: usize,
+}
+
+/// Statistiques d'utilisation d'une classe de taille.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SlabStats {
+    /// Taille des blocs de la classe.
+    pub obj_size: usize,
+    /// Nombre de pages détenues par le slab.
+    pub pages: usize,
+    /// Nombre total de blocs du slab.
+    pub total_blocks: usize,
+    /// Nombre de blocs actuellement alloués.
+    pub in_use: usize,
+    /// Maximum atteint par `in_use` depuis l'initialisation.
+    pub peak_in_use: usize,
+    /// Allocations refusées faute de page disponible.
+    pub failed: usize,
+    /// Somme des tailles demandées (`Layout::size`) des blocs alloués.
+    pub bytes_requested: usize,
+}
+
+impl SlabStats {
+    /// Octets effectivement remis aux appelants (`in_use * obj_size`).
+    pub fn bytes_allocated(&self) -> usize {
+        self.in_use * self.obj_size
+    }
+
+    /// Fragmentation interne : octets remis mais non demandés.
+    pub fn internal_fragmentation(&self) -> usize {
+        self.bytes_allocated() - self.bytes_requested
+    }
+}
+
+/// Instantané des statistiques de l'allocateur.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllocatorStats<const CLASSES: usize = N_SLABS> {
+    /// Une entrée par classe de taille, par taille croissante.
+    pub slabs: [SlabStats; CLASSES],
+    /// Allocateur de gros objets.
+    pub large: LargeStats,
+}
+
+/// Blocs vivants d'une classe de taille à un instant donné.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// Nombre de blocs alloués.
+    pub blocks: usize,
+    /// Somme des tailles demandées de ces blocs.
+    pub bytes: usize,
+}
+
+/// Instantané des blocs vivants, pris par `SlabAllocator::snapshot`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeapSnapshot<const CLASSES: usize = N_SLABS> {
+    /// Une entrée par classe de taille, par taille croissante.
+    pub slabs: [Usage; CLASSES],
+    /// Taille des blocs de chaque classe.
+    sizes: [usize; CLASSES],
+    /// Allocateur de gros objets (hors pages prêtées aux slabs).
+    pub large: Usage,
+    /// Position de la trace au moment de l'instantané.
+    #[cfg(feature = "alloc_trace")]
+    trace_seq: usize,
+}
+
+/// Blocs apparus entre deux instantanés, classe par classe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeapLeaks<const CLASSES: usize = N_SLABS> {
+    /// Une entrée par classe de taille, par taille croissante.
+    pub slabs: [Usage; CLASSES],
+    /// Taille des blocs de chaque classe.
+    sizes: [usize; CLASSES],
+    /// Allocateur de gros objets.
+    pub large: Usage,
+}
+
+impl<const CLASSES: usize> Default for AllocatorStats<CLASSES> {
+    fn default() -> Self {
+        AllocatorStats { slabs: [SlabStats::default(); CLASSES], large: LargeStats::default() }
+    }
+}
+
+impl<const CLASSES: usize> Default for HeapSnapshot<CLASSES> {
+    fn default() -> Self {
+        HeapSnapshot {
+            slabs: [Usage::default(); CLASSES],
+            sizes: [0; CLASSES],
+            large: Usage::default(),
+            #[cfg(feature = "alloc_trace")]
+            trace_seq: 0,
+        }
+    }
+}
+
+impl<const CLASSES: usize> HeapSnapshot<CLASSES> {
+    /// Compare cet instantané à un instantané antérieur `earlier`.
+    ///
+    /// Seules les augmentations comptent : une classe dont des blocs anciens
+    /// ont été libérés n'apparaît pas.
+    pub fn leaks_since(&self, earlier: &HeapSnapshot<CLASSES>) -> HeapLeaks<CLASSES> {
+        let grown = |now: &Usage, then: &Usage| Usage {
+            blocks: now.blocks.saturating_sub(then.blocks),
+            bytes: now.bytes.saturating_sub(then.bytes),
+        };
+        let mut leaks = HeapLeaks {
+            slabs: [Usage::default(); CLASSES],
+            sizes: self.sizes,
+            large: grown(&self.large, &earlier.large),
+        };
+        for (i, leak) in leaks.slabs.iter_mut().enumerate() {
+            *leak = grown(&self.slabs[i], &earlier.slabs[i]);
+        }
+        leaks
+    }
+}
+
+impl<const CLASSES: usize> HeapLeaks<CLASSES> {
+    /// Indique qu'aucune classe n'a gagné de bloc.
+    pub fn is_empty(&self) -> bool {
+        self.large.blocks == 0 && self.slabs.iter().all(|usage| usage.blocks == 0)
+    }
+}
+
+impl<const CLASSES: usize> fmt::Display for HeapLeaks<CLASSES> {
+    /// Liste les classes ayant gagné des blocs, par exemple
+    /// `class 32: 2 blocks (40 bytes), large: 1 blocks (1000 bytes)`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let classes = self.sizes.iter().zip(self.slabs.iter()).map(|(&size, usage)| (Some(size), usage));
+        let mut first = true;
+        for (class, usage) in classes.chain(core::iter::once((None, &self.large))) {
+            if usage.blocks == 0 {
+                continue;
+            }
+            if !first {
+                f.write_str(", ")?;
+            }
+            first = false;
+            match class {
+                Some(size) => write!(f, "class {}", size)?,
+                None => f.write_str("large")?,
+            }
+            write!(f, ": {} blocks ({} bytes)", usage.blocks, usage.bytes)?;
+        }
+        if first {
+            f.write_str("no leak")?;
+        }
+        Ok(())
+    }
+}
+
+/// Raison d'un échec d'allocation, retournée par `try_alloc` et retenue par
+/// `last_error` pour le gestionnaire d'OOM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocError {
+    /// Plus grand que tout le heap (ou que le plus grand bloc du buddy) :
+    /// la requête ne pourra jamais être servie.
+    TooLarge,
+    /// Plus de bloc libre dans la classe donnée (taille des blocs du slab ou
+    /// de l'ordre buddy), ou `None` dans l'allocateur de gros objets.
+    Exhausted(Option<usize>),
+    /// Alignement supérieur à `PAGE_SIZE`.
+    Misaligned,
+    /// Panne provoquée par `inject_faults` (feature `fault_injection`) : la
+    /// mémoire ne manquait pas.
+    #[cfg(feature = "fault_injection")]
+    Injected,
+}
+
+impl fmt::Display for AllocError {
+    /// Par exemple `class 32 exhausted` ou `larger than the heap`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AllocError::TooLarge => f.write_str("larger than the heap"),
+            AllocError::Exhausted(Some(size)) => write!(f, "class {} exhausted", size),
+            AllocError::Exhausted(None) => f.write_str("large heap exhausted"),
+            AllocError::Misaligned => write!(f, "alignment above {} bytes", PAGE_SIZE),
+            #[cfg(feature = "fault_injection")]
+            AllocError::Injected => f.write_str("injected fault"),
+        }
+    }
 }
 
 impl Slab {
-    /// Crée un slab non initialisé pour la taille d'objet donnée.
-    pub const fn uninit(obj_size: usize) -> Self {
-        Slab { free_list: None, obj_size, count: 0 }
+    /// Crée un slab vide (sans page) pour la classe `class`.
+    const fn for_class(class: SizeClass) -> Self {
+        let mut slab = Self::with_align(class.size, class.align());
+        slab.max_pages = class.max_pages;
+        slab
+    }
+
+    /// Crée un slab vide pour des blocs de `obj_size` octets alignés sur
+    /// `align` (puissance de deux dont `obj_size` est un multiple).
+    const fn with_align(obj_size: usize, align: usize) -> Self {
+        let first_block = align_up(size_of::<SlabPage>(), align);
+        assert!(first_block + obj_size <= PAGE_SIZE);
+        let capacity = (PAGE_SIZE - first_block) / obj_size;
+        #[cfg(feature = "slab_debug")]
+        assert!(capacity <= debug::MAX_BLOCKS);
+        Slab {
+            partial: null_mut(),
+            full: null_mut(),
+            obj_size,
+            first_block,
+            capacity,
+            pages: 0,
+            max_pages: usize::MAX,
+            in_use: 0,
+            peak: 0,
+            failed: 0,
+            requested: 0,
+        }
+    }
+
+    /// Ajoute au slab une page neuve.
+    ///
+    /// Les blocs ne sont pas chaînés d'avance : `alloc` les prend un à un
+    /// grâce au compteur `bump` de la page.
+    ///
+    /// # Safety
+    /// - `page` doit pointer vers `PAGE_SIZE` octets valides, alignés sur
+    ///   `PAGE_SIZE`, et réservés à ce slab jusqu'à leur restitution.
+    unsafe fn add_page(&mut self, page: Page) {
+        let header = page.addr as *mut SlabPage;
+        header.write(SlabPage {
+            prev: null_mut(),
+            next: null_mut(),
+            free_list: null_mut(),
+            bump: 0,
+            in_use: 0,
+            zeroed: page.zeroed,
+            #[cfg(feature = "slab_debug")]
+            guard: debug::PageGuard::new(self.obj_size),
+        });
+        Self::push(&mut self.partial, header);
+        self.pages += 1;
     }
 
-    /// Initialise la liste libre en découpant la région de mémoire spécifiée.
+    /// Alloue un bloc de ce slab pour une requête décrite par `layout`.
+    ///
+    /// Si aucune page n'a de bloc libre, `grow` est appelé pour obtenir une
+    /// page neuve, sauf si le slab a atteint `max_pages`. Retourne le bloc (`null_mut()` si `grow` n'en fournit pas)
+    /// et `true` si son contenu est garanti nul.
     ///
     /// # Safety
-    /// - Doit être appelé **exactement une fois** par slab avant toute allocation ou libération.
-    /// - `heap_base` doit pointer vers un segment de mémoire valide d'au moins
-    ///   `obj_size * slab_count(obj_size)` octets.
-    /// - Aucune autre référence mutable ne doit exister sur cette région pendant l'initialisation.
-    unsafe fn init(&mut self, heap_base: *mut u8) {
-        let mut ptr = heap_base;
-        let cnt = slab_count(self.obj_size);
-        self.count = cnt;
+    /// - Appel protégé par un `Mutex` pour éviter les accès concurrents.
+    /// - `grow` doit respecter le contrat de `add_page`.
+    unsafe fn alloc(&mut self, layout: Layout, grow: impl FnOnce() -> Option<Page>) -> (*mut u8, bool) {
+        if self.partial.is_null() {
+            let page = if self.pages < self.max_pages { grow() } else { None };
+            match page {
+                Some(page) => self.add_page(page),
+                None => {
+                    self.failed += 1;
+                    return (null_mut(), false);
+                }
+            }
+        }
 
-        // Chaînage des blocs : chaque bloc contient au début l'adresse du bloc suivant.
-        for i in 0..cnt {
-            let next = if i + 1 < cnt { ptr.add(self.obj_size) } else { null_mut() };
-            (ptr as *mut *mut u8).write(next);
-            ptr = ptr.add(self.obj_size);
+        let page = self.partial;
+        let recycled = !(*page).free_list.is_null();
+        let (block, zeroed) = if recycled {
+            let block = (*page).free_list;
+            (*page).free_list = (block as *mut *mut u8).read();
+            (block, false)
+        } else {
+            let block = (page as *mut u8).add(self.first_block + (*page).bump * self.obj_size);
+            (*page).bump += 1;
+            (block, (*page).zeroed)
+        };
+        (*page).in_use += 1;
+        #[cfg(feature = "slab_debug")]
+        debug::on_alloc(&mut (*page).guard, self.geometry(), block, recycled, &layout);
+        if self.is_full(page) {
+            Self::unlink(&mut self.partial, page);
+            Self::push(&mut self.full, page);
         }
-        self.free_list = Some(heap_base);
+
+        self.in_use += 1;
+        self.peak = self.peak.max(self.in_use);
+        self.requested += layout.size();
+        (block, zeroed)
     }
 
-    /// Alloue un bloc de ce slab.
+    /// Libère un bloc préalablement alloué avec `layout`.
     ///
-    /// Retourne un pointeur vers un bloc libre, ou `null_mut()` si épuisé.
+    /// Une page redevenue entièrement libre est rendue via `release`, sauf
+    /// s'il s'agit de la dernière page du slab. Avec `slab_debug`, le bloc
+    /// est vérifié (page, bitmap, zone rouge) puis empoisonné.
     ///
     /// # Safety
-    /// - Doit être appelé **après** un unique appel à `init`.
+    /// - `ptr` doit provenir d'un appel antérieur à `alloc` pour ce slab.
     /// - Appel protégé par un `Mutex` pour éviter les accès concurrents.
-    /// - L'alignement retourné garantit au moins l'alignement de `usize`.
-    unsafe fn alloc(&mut self) -> *mut u8 {
-        match self.free_list {
-            Some(block) => {
-                let next = (block as *mut *mut u8).read();
-                self.free_list = if next.is_null() { None } else { Some(next) };
-                block
+    unsafe fn dealloc(&mut self, ptr: *mut u8, layout: Layout, release: impl FnOnce(*mut u8)) {
+        let page = Self::page_of(ptr);
+        #[cfg(feature = "slab_debug")]
+        debug::on_dealloc(core::ptr::addr_of_mut!((*page).guard), self.geometry(), ptr, &layout);
+        if self.is_full(page) {
+            Self::unlink(&mut self.full, page);
+            Self::push(&mut self.partial, page);
+        }
+        (ptr as *mut *mut u8).write((*page).free_list);
+        (*page).free_list = ptr;
+        (*page).in_use -= 1;
+
+        self.in_use -= 1;
+        self.requested -= layout.size();
+
+        if (*page).in_use == 0 && self.pages > 1 {
+            Self::unlink(&mut self.partial, page);
+            self.pages -= 1;
+            Self::release_page(page, release);
+        }
+    }
+
+    /// Rend via `release` toutes les pages du slab, dernière comprise ; le
+    /// slab se retrouve vide, comme à sa création.
+    ///
+    /// # Safety
+    /// - Aucun bloc du slab ne doit plus être utilisé.
+    /// - `release` doit respecter le contrat de `dealloc`.
+    unsafe fn release_pages(&mut self, release: impl Fn(*mut u8)) {
+        for list in [&mut self.partial, &mut self.full] {
+            while !list.is_null() {
+                let page = *list;
+                Self::unlink(list, page);
+                Self::release_page(page, &release);
             }
-            None => null_mut(),
         }
+        self.pages = 0;
+        self.in_use = 0;
+        self.requested = 0;
     }
 
-    /// Libère un bloc préalablement alloué.
+    /// Change sur place la taille demandée d'un bloc alloué avec `layout`.
+    ///
+    /// Le bloc doit rester dans ce slab : seules les statistiques (et, avec
+    /// `slab_debug`, la zone rouge) sont mises à jour.
     ///
     /// # Safety
     /// - `ptr` doit provenir d'un appel antérieur à `alloc` pour ce slab.
-    /// - Appel protégé par un `Mutex` pour éviter les accès concurrents.
-    unsafe fn dealloc(&mut self, ptr: *mut u8) {
-        let old_head = self.free_list.unwrap_or_default();
-        (ptr as *mut *mut u8).write(old_head);
-        self.free_list = Some(ptr);
+    /// - `new_size` (plus la zone rouge) ne doit pas dépasser `obj_size`.
+    #[cfg(any(feature = "slab_debug", not(feature = "magazines")))]
+    #[cfg_attr(not(feature = "slab_debug"), allow(unused_variables))]
+    unsafe fn resize(&mut self, ptr: *mut u8, layout: Layout, new_size: usize) {
+        #[cfg(feature = "slab_debug")]
+        debug::on_resize(
+            core::ptr::addr_of_mut!((*Self::page_of(ptr)).guard),
+            self.geometry(),
+            ptr,
+            &layout,
+            new_size,
+        );
+        // Avec `magazines`, les octets demandés sont comptés par les magazines.
+        #[cfg(not(feature = "magazines"))]
+        {
+            self.requested = self.requested - layout.size() + new_size;
+        }
+    }
+
+    /// Avec `slab_debug` et `magazines`, un bloc gardé en magazine est libre
+    /// pour les vérifications, bien que le slab le compte alloué : `check_in`
+    /// vérifie un bloc qui y entre comme `dealloc` (page, bitmap, zone rouge
+    /// de `layout`), puis l'empoisonne.
+    ///
+    /// # Safety
+    /// `ptr` doit provenir de ce slab, verrouillé.
+    #[cfg(all(feature = "slab_debug", feature = "magazines"))]
+    unsafe fn check_in(&self, ptr: *mut u8, layout: &Layout) {
+        debug::on_dealloc(core::ptr::addr_of_mut!((*Self::page_of(ptr)).guard), self.geometry(), ptr, layout);
+    }
+
+    /// Inverse de `check_in` : vérifie le poison d'un bloc qui quitte un
+    /// magazine, le marque alloué et pose sa zone rouge pour `layout`.
+    ///
+    /// # Safety
+    /// `block` doit être passé par `check_in` de ce slab, verrouillé.
+    #[cfg(all(feature = "slab_debug", feature = "magazines"))]
+    unsafe fn check_out(&self, block: *mut u8, layout: &Layout) {
+        debug::on_alloc(&mut (*Self::page_of(block)).guard, self.geometry(), block, true, layout);
+    }
+
+    /// Rend `page`, retirée de ses listes, via `release`. Avec `slab_debug`,
+    /// sa garde est d'abord effacée.
+    ///
+    /// # Safety
+    /// `release` doit respecter le contrat de `dealloc`.
+    unsafe fn release_page(page: *mut SlabPage, release: impl FnOnce(*mut u8)) {
+        #[cfg(feature = "slab_debug")]
+        debug::on_release(core::ptr::addr_of_mut!((*page).guard));
+        release(page as *mut u8);
+    }
+
+    /// En-tête de la page contenant `ptr`.
+    fn page_of(ptr: *mut u8) -> *mut SlabPage {
+        (ptr as usize & !(PAGE_SIZE - 1)) as *mut SlabPage
+    }
+
+    /// Indique si tous les blocs de `page` sont alloués.
+    unsafe fn is_full(&self, page: *mut SlabPage) -> bool {
+        (*page).free_list.is_null() && (*page).bump == self.capacity
+    }
+
+    /// Géométrie du slab utilisée par les vérifications de `slab_debug`.
+    #[cfg(feature = "slab_debug")]
+    fn geometry(&self) -> debug::Geometry {
+        debug::Geometry {
+            obj_size: self.obj_size,
+            first_block: self.first_block,
+            capacity: self.capacity,
+        }
+    }
+
+    /// Insère `page` en tête de `list`.
+    unsafe fn push(list: &mut *mut SlabPage, page: *mut SlabPage) {
+        (*page).prev = null_mut();
+        (*page).next = *list;
+        if !list.is_null() {
+            (**list).prev = page;
+        }
+        *list = page;
+    }
+
+    /// Retire `page` de `list`.
+    unsafe fn unlink(list: &mut *mut SlabPage, page: *mut SlabPage) {
+        let (prev, next) = ((*page).prev, (*page).next);
+        if prev.is_null() {
+            *list = next;
+        } else {
+            (*prev).next = next;
+        }
+        if !next.is_null() {
+            (*next).prev = prev;
+        }
+    }
+
+    /// Retourne les statistiques courantes de ce slab.
+    fn stats(&self) -> SlabStats {
+        SlabStats {
+            obj_size: self.obj_size,
+            pages: self.pages,
+            total_blocks: self.pages * self.capacity,
+            in_use: self.in_use,
+            peak_in_use: self.peak,
+            failed: self.failed,
+            bytes_requested: self.requested,
+        }
+    }
+
+    /// Relève l'occupation de `page`.
+    ///
+    /// # Safety
+    /// `page` doit appartenir à ce slab.
+    unsafe fn occupancy(&self, page: *mut SlabPage) -> Occupancy {
+        let mut occupancy = Occupancy { free: [false; MAX_PAGE_BLOCKS], free_list: 0, longest_run: 0 };
+        for free in &mut occupancy.free[(*page).bump..self.capacity] {
+            *free = true;
+        }
+        let mut block = (*page).free_list;
+        while !block.is_null() {
+            let index = (block as usize - page as usize - self.first_block) / self.obj_size;
+            occupancy.free[index] = true;
+            occupancy.free_list += 1;
+            block = (block as *mut *mut u8).read();
+        }
+        let mut run = 0;
+        for &free in &occupancy.free[..self.capacity] {
+            run = if free { run + 1 } else { 0 };
+            occupancy.longest_run = occupancy.longest_run.max(run);
+        }
+        occupancy
+    }
+
+    /// Écrit la carte d'occupation du slab (format de `SlabAllocator::write_map`).
+    fn write_map(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        // SAFETY: le slab est verrouillé par l'appelant, ses listes sont stables.
+        let pages = || unsafe { page_list(self.partial).chain(page_list(self.full)) };
+        let per_char = self.capacity.div_ceil(MAP_WIDTH);
+        let (mut free_list, mut longest_run) = (0, 0);
+        for page in pages() {
+            // SAFETY: `page` vient des listes de ce slab.
+            let occupancy = unsafe { self.occupancy(page) };
+            free_list += occupancy.free_list;
+            longest_run = longest_run.max(occupancy.longest_run);
+        }
+        writeln!(
+            out,
+            "class={} pages={} used={}/{} free_list={} longest_run={} blocks_per_char={}",
+            self.obj_size,
+            self.pages,
+            self.in_use,
+            self.pages * self.capacity,
+            free_list,
+            longest_run,
+            per_char
+        )?;
+        for page in pages() {
+            // SAFETY: `page` vient des listes de ce slab.
+            let (occupancy, in_use) = unsafe { (self.occupancy(page), (*page).in_use) };
+            writeln!(
+                out,
+                "  page={:#x} used={}/{} free_list={} longest_run={}",
+                page as usize, in_use, self.capacity, occupancy.free_list, occupancy.longest_run
+            )?;
+            out.write_str("  ")?;
+            for group in occupancy.free[..self.capacity].chunks(per_char) {
+                let free = group.iter().filter(|&&free| free).count();
+                out.write_char(match free {
+                    0 => '#',
+                    n if n == group.len() => '.',
+                    _ => '+',
+                })?;
+            }
+            out.write_char('\n')?;
+        }
+        Ok(())
     }
 }
 
-/// Allocateur global basé sur plusieurs slabs de tailles décroissantes.
+impl PageSource for IrqMutex<LinkedListAllocator> {
+    fn alloc_page(&self) -> Option<Page> {
+        self.lock().alloc_page()
+    }
+
+    unsafe fn dealloc_page(&self, page: *mut u8) {
+        self.lock().dealloc_page(page)
+    }
+}
+
+/// Allocateur global basé sur `CLASSES` slabs de tailles croissantes.
 ///
 /// Implémente le trait `GlobalAlloc` pour prendre en charge les allocations
-/// et libérations via la macro `#[global_allocator]`.
-pub struct SimpleAllocator {
-    slabs: [Mutex<Slab>; N_SLABS],
+/// et libérations via la macro `#[global_allocator]`. Les classes sont
+/// fixées par `with_classes`, utilisable dans un initialiseur de `static`.
+pub struct SlabAllocator<const CLASSES: usize> {
+    /// Classes de taille, par taille croissante.
+    classes: [SizeClass; CLASSES],
+    slabs: [IrqMutex<Slab>; CLASSES],
+    /// Allocateur de second niveau pour les gros objets et, par défaut, les
+    /// pages des slabs.
+    large: IrqMutex<LinkedListAllocator>,
+    /// Source de pages externe installée par `set_page_source`.
+    page_source: IrqMutex<Option<&'static (dyn PageSource + Sync)>>,
+    /// Garde d'initialisation unique du heap (`init` ou heap statique).
+    heap_ready: Once<()>,
+    /// Heap statique fourni par `with_static_heap` (`HEAP` sinon).
+    #[cfg(feature = "static_heap")]
+    static_heap: Option<(*mut u8, usize)>,
+    /// Callbacks de récupération appelés avant de déclarer l'OOM.
+    shrinkers: shrink::Registry,
+    /// Raison du dernier échec d'allocation.
+    last_error: IrqMutex<Option<AllocError>>,
+    /// Derniers événements d'allocation (feature `alloc_trace`).
+    #[cfg(feature = "alloc_trace")]
+    trace: IrqMutex<trace::Ring>,
+    /// Pannes d'allocation simulées (feature `fault_injection`).
+    #[cfg(feature = "fault_injection")]
+    faults: IrqMutex<fault::Injector>,
+    /// Caches de blocs par CPU devant les slabs (feature `magazines`).
+    #[cfg(feature = "magazines")]
+    magazines: magazine::Magazines<CLASSES>,
+    /// Mémoire fantôme du heap (feature `kasan`).
+    #[cfg(feature = "kasan")]
+    kasan: IrqMutex<kasan::Shadow>,
 }
 
-unsafe impl Sync for SimpleAllocator {}
-unsafe impl Send for SimpleAllocator {}
+/// Allocateur à slabs sur les classes `DEFAULT_CLASSES` (16 / 32 / 64 / 128 o).
+pub type SimpleAllocator = SlabAllocator<N_SLABS>;
+
+unsafe impl<const CLASSES: usize> Sync for SlabAllocator<CLASSES> {}
+unsafe impl<const CLASSES: usize> Send for SlabAllocator<CLASSES> {}
 
 impl Default for SimpleAllocator {
     fn default() -> Self {
@@ -112,80 +822,606 @@ impl Default for SimpleAllocator {
 }
 
 impl SimpleAllocator {
-    /// Construit un allocateur avec tous les slabs initialement non configurés.
+    /// Construit un allocateur sur `DEFAULT_CLASSES`, tous les slabs vides.
     pub const fn new() -> Self {
-        SimpleAllocator {
-            slabs: [
-                Mutex::new(Slab::uninit(SLAB_SIZES[0])),
-                Mutex::new(Slab::uninit(SLAB_SIZES[1])),
-                Mutex::new(Slab::uninit(SLAB_SIZES[2])),
-                Mutex::new(Slab::uninit(SLAB_SIZES[3])),
-            ],
+        Self::with_classes(DEFAULT_CLASSES)
+    }
+}
+
+impl<const CLASSES: usize> SlabAllocator<CLASSES> {
+    /// Construit un allocateur sur les classes `classes`, tous les slabs vides.
+    ///
+    /// Les tailles doivent être strictement croissantes, multiples de 8 et
+    /// tenir dans une page après l'en-tête de slab (au moins 16 avec
+    /// `slab_debug`) ; sinon la construction échoue à la compilation.
+    pub const fn with_classes(classes: [SizeClass; CLASSES]) -> Self {
+        // Slabs provisoires, remplacés un à un par ceux des classes.
+        let mut slabs = [const { IrqMutex::new(Slab::for_class(DEFAULT_CLASSES[0])) }; CLASSES];
+        let mut i = 0;
+        while i < CLASSES {
+            let size = classes[i].size;
+            assert!(size >= MIN_CLASS_SIZE && size.is_multiple_of(MIN_CLASS_SIZE), "size classes must be multiples of 8");
+            assert!(i == 0 || classes[i - 1].size < size, "size classes must be strictly increasing");
+            slabs[i] = IrqMutex::new(Slab::for_class(classes[i]));
+            i += 1;
         }
+        SlabAllocator {
+            classes,
+            slabs,
+            large: IrqMutex::new(LinkedListAllocator::new()),
+            page_source: IrqMutex::new(None),
+            heap_ready: Once::new(),
+            #[cfg(feature = "static_heap")]
+            static_heap: None,
+            shrinkers: shrink::Registry::new(),
+            last_error: IrqMutex::new(None),
+            #[cfg(feature = "alloc_trace")]
+            trace: IrqMutex::new(trace::Ring::new()),
+            #[cfg(feature = "fault_injection")]
+            faults: IrqMutex::new(fault::Injector::new()),
+            #[cfg(feature = "magazines")]
+            magazines: magazine::Magazines::new(),
+            #[cfg(feature = "kasan")]
+            kasan: IrqMutex::new(kasan::Shadow::new()),
+        }
+    }
+
+    /// Remplace le heap statique de secours (`HEAP`, 64 KiB) par la région
+    /// `heap..heap + size`, utilisée si `init` n'est jamais appelé.
+    ///
+    /// # Safety
+    /// La région doit être valide, réservée à cet allocateur et remplie de
+    /// zéros (une `static` en `.bss`).
+    #[cfg(feature = "static_heap")]
+    pub const unsafe fn with_static_heap(mut self, heap: *mut u8, size: usize) -> Self {
+        self.static_heap = Some((heap, size));
+        self
     }
 
-    /// Initialise explicitement la zone de heap utilisée par l'allocateur.
+    /// Initialise l'allocateur sur la région `heap_start..heap_start + heap_size`.
     ///
-    /// Cette implémentation conserve un heap statique interne, les paramètres
-    /// sont donc simplement ignorés pour compatibilité.
+    /// La région entière est confiée à l'allocateur de gros objets, dans
+    /// lequel les slabs prélèvent leurs pages au fil des besoins. Elle peut
+    /// provenir de n'importe quelle plage virtuelle mappée (carte mémoire du
+    /// bootloader, pages dédiées…).
+    ///
+    /// Seul le premier appel est pris en compte ; les suivants sont sans effet.
     ///
     /// # Safety
     /// Caller must guarantee that `heap_start..heap_start + heap_size`
-    /// is valid RAM and not used elsewhere. Call exactly once, before any
-    /// allocation occurs.
-    pub unsafe fn init(&self, _heap_start: usize, _heap_size: usize) {
-        // Le heap est statique, aucune action n’est nécessaire ici.
+    /// is valid RAM and not used elsewhere, before any allocation occurs.
+    pub unsafe fn init(&self, heap_start: usize, heap_size: usize) {
+        self.heap_ready.call_once(|| self.init_region(heap_start, heap_size, false));
+    }
+
+    /// Comme `init`, pour une région dont l'appelant garantit qu'elle est
+    /// entièrement remplie de zéros (frames fraîchement effacées, `.bss`…).
+    ///
+    /// `alloc_zeroed` peut alors servir sans les effacer les blocs pris dans
+    /// une partie du heap qui n'a encore jamais été distribuée.
+    ///
+    /// # Safety
+    /// Mêmes conditions que `init` ; la région doit en plus être nulle.
+    pub unsafe fn init_zeroed(&self, heap_start: usize, heap_size: usize) {
+        self.heap_ready.call_once(|| self.init_region(heap_start, heap_size, true));
+    }
+
+    /// Installe une source de pages externe pour les slabs (allocateur de
+    /// frames, plage virtuelle dédiée…) à la place du heap.
+    ///
+    /// # Safety
+    /// Doit être appelé avant toute allocation servie par un slab : les pages
+    /// déjà détenues seraient sinon rendues à la mauvaise source.
+    pub unsafe fn set_page_source(&self, source: &'static (dyn PageSource + Sync)) {
+        *self.page_source.lock() = Some(source);
+    }
+
+    /// Alloue `count` pages contiguës alignées sur `PAGE_SIZE`.
+    ///
+    /// Retourne `null_mut()` si `count` vaut 0 ou si le heap est épuisé.
+    ///
+    /// # Safety
+    /// Le bloc doit être rendu par `dealloc_pages` avec le même `count`.
+    pub unsafe fn alloc_pages(&self, count: usize) -> *mut u8 {
+        match Self::page_layout(count) {
+            Some(layout) => self.alloc(layout),
+            None => null_mut(),
+        }
+    }
+
+    /// Rend `count` pages obtenues par `alloc_pages`.
+    ///
+    /// # Safety
+    /// `ptr` et `count` doivent correspondre à un appel antérieur à `alloc_pages`.
+    pub unsafe fn dealloc_pages(&self, ptr: *mut u8, count: usize) {
+        if let Some(layout) = Self::page_layout(count) {
+            self.dealloc(ptr, layout);
+        }
+    }
+
+    /// Retourne un instantané des statistiques de chaque classe de taille et
+    /// de l'allocateur de gros objets.
+    pub fn stats(&self) -> AllocatorStats<CLASSES> {
+        let mut stats = AllocatorStats::default();
+        for (i, entry) in stats.slabs.iter_mut().enumerate() {
+            *entry = self.class_stats(i);
+        }
+        stats.large = self.large.lock().stats();
+        stats
+    }
+
+    /// Affiche les statistiques courantes sur le port série.
+    pub fn print_stats(&self) {
+        self.stats().print();
+    }
+
+    /// Écrit dans `out` la carte d'occupation de chaque slab : une ligne par
+    /// classe (totaux), puis pour chaque page ses compteurs et une ligne d'un
+    /// caractère par bloc, ou par groupe de `blocks_per_char` blocs pour que la
+    /// page tienne sur `MAP_WIDTH` colonnes :
+    ///
+    /// ```text
+    /// heap-map '#' used '.' free '+' mixed
+    /// class=32 pages=2 used=130/252 free_list=5 longest_run=118 blocks_per_char=2
+    ///   page=0x444400003000 used=4/126 free_list=5 longest_run=118
+    ///   ++++...........................................................
+    ///   page=0x444400002000 used=126/126 free_list=0 longest_run=0
+    ///   ###############################################################
+    /// ```
+    ///
+    /// Les blocs libres sont ceux de la liste libre et ceux jamais distribués ;
+    /// les blocs gardés en magazine (feature `magazines`) apparaissent alloués.
+    /// Les pages partielles précèdent les pages pleines. Chaque slab reste
+    /// verrouillé pendant son écriture : `out` ne doit pas allouer. Pour
+    /// l'écran : `ALLOCATOR.write_map(&mut *vga_buffer::WRITER.lock())`.
+    pub fn write_map(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        writeln!(out, "heap-map '#' used '.' free '+' mixed")?;
+        for slab in self.slabs.iter() {
+            slab.lock().write_map(out)?;
+        }
+        Ok(())
+    }
+
+    /// Envoie la carte d'occupation des slabs sur `serial::SERIAL1`.
+    pub fn dump_map(&self) {
+        self.write_map(&mut *crate::serial::SERIAL1.lock())
+            .expect("Printing to serial failed");
+    }
+
+    /// Relève les blocs vivants de chaque classe, pour une comparaison
+    /// ultérieure avec `HeapSnapshot::leaks_since`.
+    pub fn snapshot(&self) -> HeapSnapshot<CLASSES> {
+        let mut snapshot = HeapSnapshot::default();
+        for (i, usage) in snapshot.slabs.iter_mut().enumerate() {
+            let stats = self.class_stats(i);
+            *usage = Usage { blocks: stats.in_use, bytes: stats.bytes_requested };
+            snapshot.sizes[i] = stats.obj_size;
+        }
+        let large = self.large.lock().stats();
+        snapshot.large = Usage { blocks: large.in_use, bytes: large.bytes_requested };
+        #[cfg(feature = "alloc_trace")]
+        {
+            snapshot.trace_seq = self.trace.lock().count();
+        }
+        snapshot
+    }
+
+    /// Écrit dans `out` les derniers événements d'allocation, une ligne par
+    /// événement (format décrit dans le module `trace`).
+    #[cfg(feature = "alloc_trace")]
+    pub fn write_trace(&self, out: &mut dyn core::fmt::Write) -> core::fmt::Result {
+        self.trace.lock().write(out)
+    }
+
+    /// Envoie la trace des allocations sur `serial::SERIAL1`.
+    #[cfg(feature = "alloc_trace")]
+    pub fn dump_trace(&self) {
+        self.write_trace(&mut *crate::serial::SERIAL1.lock())
+            .expect("Printing to serial failed");
+    }
+
+    /// Envoie sur `serial::SERIAL1` les blocs alloués depuis `since` et
+    /// toujours vivants, tels que la trace les a retenus.
+    #[cfg(feature = "alloc_trace")]
+    pub fn dump_leaked_blocks(&self, since: &HeapSnapshot<CLASSES>) {
+        self.trace
+            .lock()
+            .write_live_since(since.trace_seq, &mut *crate::serial::SERIAL1.lock())
+            .expect("Printing to serial failed");
+    }
+
+    /// Rend aux slabs les blocs gardés dans les magazines du CPU courant, pour
+    /// qu'ils puissent rendre leurs pages vides. Retourne le nombre de blocs
+    /// rendus.
+    #[cfg(feature = "magazines")]
+    pub fn drain_magazines(&self) -> usize {
+        let release = |page| unsafe { self.pages().dealloc_page(page) };
+        // SAFETY: les pages vides retournent à la source qui les a fournies.
+        (0..CLASSES).map(|i| unsafe { self.magazines.drain(i, || self.slabs[i].lock(), release) }).sum()
+    }
+
+    /// Alloue un bloc pour `layout` comme `alloc`, mais indique la raison
+    /// d'un échec au lieu de retourner `null_mut()`.
+    ///
+    /// Le bloc se rend par `dealloc` avec le même `layout`.
+    pub fn try_alloc(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        // SAFETY: le heap n'est confié à l'allocateur que par `init` (unsafe).
+        let ptr = unsafe { self.allocate(layout) }.map(|(ptr, _)| ptr);
+        self.record_alloc(ptr.unwrap_or(null_mut()), &layout);
+        // SAFETY: `allocate` ne retourne jamais `Ok` avec un pointeur nul.
+        ptr.map(|ptr| unsafe { NonNull::new_unchecked(ptr) })
+    }
+
+    /// Raison du dernier échec d'allocation (`alloc`, `realloc` ou
+    /// `try_alloc`), s'il y en a eu un.
+    pub fn last_error(&self) -> Option<AllocError> {
+        *self.last_error.lock()
+    }
+
+    /// Enregistre un shrinker, appelé quand une allocation échoue faute de
+    /// mémoire ; l'allocation est retentée tant qu'un shrinker rend des octets.
+    pub fn register_shrinker(&self, shrinker: &'static Shrinker) -> Result<(), RegistryFull> {
+        self.shrinkers.register(shrinker)
+    }
+
+    /// Compteurs des shrinkers enregistrés, dans l'ordre d'enregistrement.
+    pub fn shrinker_stats(&self) -> [Option<ShrinkerStats>; MAX_SHRINKERS] {
+        self.shrinkers.stats()
+    }
+
+    /// Arme l'injection de pannes : les allocations désignées par `fault`
+    /// retournent `null_mut()` jusqu'à `clear_faults`.
+    ///
+    /// Les allocations sont comptées à partir de cet appel ; un
+    /// redimensionnement par `realloc` compte pour une allocation.
+    #[cfg(feature = "fault_injection")]
+    pub fn inject_faults(&self, fault: Fault) {
+        self.faults.lock().arm(Some(fault));
+    }
+
+    /// Désarme l'injection de pannes et retourne le nombre d'allocations
+    /// refusées depuis `inject_faults`.
+    #[cfg(feature = "fault_injection")]
+    pub fn clear_faults(&self) -> usize {
+        self.faults.lock().arm(None)
+    }
+
+    /// Vérifie que `len` octets à partir de `ptr` peuvent être lus : ni zone
+    /// rouge, ni bloc libéré, ni partie du heap jamais distribuée. Une
+    /// violation est signalée sur `serial::SERIAL1` avec l'historique du bloc.
+    ///
+    /// Les adresses hors du heap (pile, statiques, pages d'une source externe)
+    /// ne sont pas contrôlées.
+    #[cfg(feature = "kasan")]
+    pub fn check_read(&self, ptr: *const u8, len: usize) -> Result<(), Violation> {
+        self.check_access(ptr, len, Access::Read)
+    }
+
+    /// Comme `check_read`, pour une écriture.
+    #[cfg(feature = "kasan")]
+    pub fn check_write(&self, ptr: *const u8, len: usize) -> Result<(), Violation> {
+        self.check_access(ptr, len, Access::Write)
+    }
+
+    #[cfg(feature = "kasan")]
+    fn check_access(&self, ptr: *const u8, len: usize, access: Access) -> Result<(), Violation> {
+        let shadow = self.kasan.lock();
+        let result = shadow.check(ptr as usize, len, access);
+        if let Err(violation) = &result {
+            shadow
+                .report(violation, &mut *crate::serial::SERIAL1.lock())
+                .expect("Printing to serial failed");
+        }
+        result
+    }
+
+    /// Confie `heap_start..heap_start + heap_size` à l'allocateur de gros
+    /// objets, après en avoir retiré la mémoire fantôme avec `kasan`.
+    unsafe fn init_region(&self, heap_start: usize, heap_size: usize, zeroed: bool) {
+        #[cfg(feature = "kasan")]
+        let heap_size = self.kasan.lock().init(heap_start, heap_size);
+        self.large.lock().init(heap_start, heap_size, zeroed)
+    }
+
+    /// Layout de `count` pages alignées, ou `None` si `count` est nul ou trop grand.
+    fn page_layout(count: usize) -> Option<Layout> {
+        let size = count.checked_mul(PAGE_SIZE).filter(|&size| size > 0)?;
+        Layout::from_size_align(size, PAGE_SIZE).ok()
+    }
+
+    /// Alloue un bloc pour `layout` et indique si son contenu est garanti nul.
+    unsafe fn allocate(&self, layout: Layout) -> Result<(*mut u8, bool), AllocError> {
+        if layout.align() > PAGE_SIZE {
+            return Err(self.fail(&layout));
+        }
+        #[cfg(feature = "fault_injection")]
+        if self.faults.lock().should_fail(self.class_size(&layout)) {
+            return Err(self.record_error(AllocError::Injected));
+        }
+        match self.allocate_or_reclaim(layout) {
+            (ptr, _) if ptr.is_null() => Err(self.fail(&layout)),
+            block => Ok(block),
+        }
+    }
+
+    /// Détermine pourquoi l'allocation de `layout` a échoué et le retient
+    /// pour `last_error`.
+    fn fail(&self, layout: &Layout) -> AllocError {
+        let error = if layout.align() > PAGE_SIZE {
+            AllocError::Misaligned
+        } else if let Some(size) = self.class_size(layout) {
+            AllocError::Exhausted(Some(size))
+        } else if layout.size() > self.large.lock().stats().heap_size {
+            AllocError::TooLarge
+        } else {
+            AllocError::Exhausted(None)
+        };
+        self.record_error(error)
+    }
+
+    /// Retient `error` pour `last_error` et la retourne.
+    fn record_error(&self, error: AllocError) -> AllocError {
+        *self.last_error.lock() = Some(error);
+        error
+    }
+
+    /// Consigne une allocation dans la trace et la mémoire fantôme.
+    ///
+    /// Toujours inlinée : les appelants relevés doivent être ceux de `alloc`.
+    #[inline(always)]
+    #[allow(unused_variables)]
+    fn record_alloc(&self, ptr: *mut u8, layout: &Layout) {
+        #[cfg(feature = "alloc_trace")]
+        self.trace.lock().record(trace::Op::Alloc, ptr, layout, self.class_size(layout));
+        #[cfg(feature = "kasan")]
+        if !ptr.is_null() {
+            self.kasan.lock().on_alloc(ptr as usize, layout.size(), self.block_size(layout));
+        }
+    }
+
+    /// Alloue un bloc pour `layout` ; en cas d'échec, vide les magazines du
+    /// CPU courant puis fait appel aux shrinkers, et réessaie tant qu'ils
+    /// rendent de la mémoire.
+    unsafe fn allocate_or_reclaim(&self, layout: Layout) -> (*mut u8, bool) {
+        loop {
+            let block = self.allocate_block(layout);
+            if !block.0.is_null() {
+                return block;
+            }
+            // Les blocs gardés en magazine peuvent libérer des pages.
+            #[cfg(feature = "magazines")]
+            if self.drain_magazines() > 0 {
+                continue;
+            }
+            if !self.shrinkers.reclaim(&layout) {
+                return block;
+            }
+        }
+    }
+
+    /// Alloue un bloc pour `layout`, sans injection de pannes ni récupération.
+    unsafe fn allocate_block(&self, layout: Layout) -> (*mut u8, bool) {
+        // Initialisation paresseuse sur le heap statique si `init` n'a pas été
+        // appelé : une fois faite, ce n'est plus qu'une lecture atomique.
+        // Interruptions masquées, comme sous les verrous : un gestionnaire
+        // attendrait sinon la fin d'une initialisation qu'il interrompt.
+        #[cfg(feature = "static_heap")]
+        if self.heap_ready.r#try().is_none() {
+            irq::without_interrupts(|| {
+                self.heap_ready.call_once(|| {
+                    let (heap, size) =
+                        self.static_heap.unwrap_or((core::ptr::addr_of_mut!(HEAP.0) as *mut u8, HEAP_SIZE));
+                    self.init_region(heap as usize, size, true)
+                })
+            });
+        }
+        // Sélection de la slab adaptée (taille et alignement), qui prend une
+        // nouvelle page si toutes les siennes sont pleines.
+        if let Some(i) = self.class_of(&layout) {
+            #[cfg(feature = "magazines")]
+            return self.magazines.alloc(i, layout, || self.slabs[i].lock(), || self.pages().alloc_page());
+            #[cfg(not(feature = "magazines"))]
+            return self.slabs[i].lock().alloc(layout, || self.pages().alloc_page());
+        }
+        // Gros objet ou alignement fort → liste chaînée (`null_mut()` si OOM)
+        self.large.lock().alloc(layout)
+    }
+
+    /// Statistiques du slab de la classe `i`, sans les blocs en magazine.
+    fn class_stats(&self, i: usize) -> SlabStats {
+        let stats = self.slabs[i].lock().stats();
+        #[cfg(feature = "magazines")]
+        let stats = {
+            let (cached, requested) = self.magazines.usage(i);
+            SlabStats { in_use: stats.in_use.saturating_sub(cached), bytes_requested: requested, ..stats }
+        };
+        stats
+    }
+
+    /// Indice de la classe capable de servir `layout`, s'il en existe une :
+    /// la plus petite couvrant à la fois la taille (plus la zone rouge
+    /// éventuelle) et l'alignement demandés (voir `SizeClass::align`).
+    fn class_of(&self, layout: &Layout) -> Option<usize> {
+        let needed = layout.size() + REDZONE;
+        self.classes.iter().position(|class| needed <= class.size && layout.align() <= class.align())
+    }
+
+    /// Taille des blocs de la classe servant `layout`, ou `None` pour un gros
+    /// objet.
+    fn class_size(&self, layout: &Layout) -> Option<usize> {
+        self.class_of(layout).map(|i| self.classes[i].size)
+    }
+
+    /// Taille du bloc réellement réservé pour `layout` : sa classe de slab,
+    /// ou la taille arrondie par l'allocateur de gros objets.
+    #[cfg(feature = "kasan")]
+    fn block_size(&self, layout: &Layout) -> usize {
+        self.class_size(layout).unwrap_or_else(|| LinkedListAllocator::size_align(*layout).0)
+    }
+
+    /// Retourne la source de pages des slabs.
+    fn pages(&self) -> &dyn PageSource {
+        match *self.page_source.lock() {
+            Some(source) => source,
+            None => &self.large,
+        }
     }
 }
 
-unsafe impl GlobalAlloc for SimpleAllocator {
+impl<const CLASSES: usize> AllocatorStats<CLASSES> {
+    /// Affiche un tableau par classe de taille sur le port série.
+    ///
+    /// `waste` est la fragmentation interne (octets remis mais non demandés).
+    pub fn print(&self) {
+        crate::serial_println!(
+            "{:>6} {:>5} {:>7} {:>7} {:>7} {:>7} {:>10} {:>10} {:>8}",
+            "class", "pages", "blocks", "in_use", "peak", "failed", "requested", "allocated", "waste"
+        );
+        for slab in self.slabs.iter() {
+            crate::serial_println!(
+                "{:>6} {:>5} {:>7} {:>7} {:>7} {:>7} {:>10} {:>10} {:>8}",
+                slab.obj_size,
+                slab.pages,
+                slab.total_blocks,
+                slab.in_use,
+                slab.peak_in_use,
+                slab.failed,
+                slab.bytes_requested,
+                slab.bytes_allocated(),
+                slab.internal_fragmentation()
+            );
+        }
+        let large = &self.large;
+        crate::serial_println!(
+            "{:>6} {:>5} {:>7} {:>7} {:>7} {:>7} {:>10} {:>10} {:>8}",
+            "large",
+            "-",
+            "-",
+            large.in_use,
+            large.peak_in_use,
+            large.failed,
+            large.bytes_requested,
+            large.bytes_allocated,
+            large.bytes_allocated - large.bytes_requested
+        );
+        crate::serial_println!(
+            "large heap: {} bytes, {} free, largest free region {}, {} slab pages",
+            large.heap_size, large.free_bytes, large.largest_free, large.slab_pages
+        );
+    }
+}
+
+unsafe impl<const CLASSES: usize> GlobalAlloc for SlabAllocator<CLASSES> {
     /// Alloue un bloc de mémoire correspondant à `layout`.
     ///
     /// # Safety
     /// - La région de heap globale doit être invalide autrement.
     /// - Cette fonction utilise des blocs `unsafe` internes protégés par `Mutex`.
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-        let base = core::ptr::addr_of_mut!(HEAP) as *mut u8;
-        // Initialisation paresseuse : configure chaque slab si nécessaire.
-        for (i, &size) in SLAB_SIZES.iter().enumerate() {
-            let mut slab = self.slabs[i].lock();
-            if slab.count == 0 {
-                let offset = i * slab_count(size) * size;
-                slab.init(base.add(offset));
-            }
-        }
-        // Sélection de la slab adaptée
-        for (i, &size) in SLAB_SIZES.iter().enumerate() {
-            if layout.size() <= size {
-                return self.slabs[i].lock().alloc();
-            }
+        let (ptr, _) = self.allocate(layout).unwrap_or((null_mut(), false));
+        self.record_alloc(ptr, &layout);
+        ptr
+    }
+
+    /// Alloue un bloc rempli de zéros.
+    ///
+    /// L'effacement est évité pour les blocs neufs pris dans de la mémoire
+    /// connue nulle (page de slab ou partie du heap jamais distribuée).
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let (ptr, zeroed) = self.allocate(layout).unwrap_or((null_mut(), false));
+        self.record_alloc(ptr, &layout);
+        if !ptr.is_null() && !zeroed {
+            ptr.write_bytes(0, layout.size());
         }
-        // Pas de slab adaptée → `null_mut()` (OOM)
-        null_mut()
+        ptr
     }
 
     /// Libère le bloc pointé par `ptr` avec la taille `layout`.
     ///
+    /// Le bloc est rendu au slab de sa classe (qui rend ses pages vides à la
+    /// source de pages), ou à l'allocateur de gros objets.
+    ///
     /// # Safety
     /// - `ptr` et `layout` doivent correspondre à un appel antérieur à `alloc`.
     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
-        for (i, &size) in SLAB_SIZES.iter().enumerate() {
-            if layout.size() <= size {
-                return self.slabs[i].lock().dealloc(ptr);
+        #[cfg(feature = "alloc_trace")]
+        self.trace.lock().record(trace::Op::Dealloc, ptr, &layout, self.class_size(&layout));
+        #[cfg(feature = "kasan")]
+        self.kasan.lock().on_dealloc(ptr as usize, self.block_size(&layout));
+        if let Some(i) = self.class_of(&layout) {
+            let release = |page| self.pages().dealloc_page(page);
+            #[cfg(feature = "magazines")]
+            return self.magazines.dealloc(i, ptr, layout, || self.slabs[i].lock(), release);
+            #[cfg(not(feature = "magazines"))]
+            return self.slabs[i].lock().dealloc(ptr, layout, release);
+        }
+        self.large.lock().dealloc(ptr, layout)
+    }
+
+    /// Redimensionne le bloc `ptr` à `new_size` octets.
+    ///
+    /// Le bloc reste en place tant que la nouvelle taille relève de la même
+    /// classe de slab, ou, pour un gros objet, si la région suit (réduction,
+    /// ou région libre contiguë pour un agrandissement). Sinon il est déplacé.
+    ///
+    /// # Safety
+    /// Voir `GlobalAlloc::realloc`.
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        #[cfg(feature = "fault_injection")]
+        if self.faults.lock().should_fail(self.class_size(&new_layout)) {
+            self.record_error(AllocError::Injected);
+            return null_mut();
+        }
+        let in_place = match (self.class_of(&layout), self.class_of(&new_layout)) {
+            (Some(old), Some(new)) if old == new => {
+                #[cfg(feature = "magazines")]
+                self.magazines.account(old, new_size as isize - layout.size() as isize);
+                #[cfg(any(feature = "slab_debug", not(feature = "magazines")))]
+                self.slabs[old].lock().resize(ptr, layout, new_size);
+                true
+            }
+            (None, None) => self.large.lock().resize(ptr, layout, new_size),
+            _ => false,
+        };
+        if in_place {
+            #[cfg(feature = "alloc_trace")]
+            {
+                let mut trace = self.trace.lock();
+                trace.record(trace::Op::Dealloc, ptr, &layout, self.class_size(&layout));
+                trace.record(trace::Op::Alloc, ptr, &new_layout, self.class_size(&new_layout));
             }
+            #[cfg(feature = "kasan")]
+            self.kasan.lock().on_resize(ptr as usize, new_size, self.block_size(&layout), self.block_size(&new_layout));
+            return ptr;
         }
-        // Si la taille est trop grande, on ignore
+
+        let (new_ptr, _) = self.allocate_or_reclaim(new_layout);
+        if new_ptr.is_null() {
+            self.fail(&new_layout);
+        }
+        self.record_alloc(new_ptr, &new_layout);
+        if !new_ptr.is_null() {
+            core::ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+            self.dealloc(ptr, layout);
+        }
+        new_ptr
     }
 }
 
 /// Gestionnaire global d'erreur d'allocation (OOM).
 ///
-/// Panique en détaillant la `layout` requise.
-/// Désactivé si la feature `oom_integration` est activée.
-#[cfg(not(feature = "oom_integration"))]
+/// Panique en détaillant la `layout` requise et la raison de l'échec retenue
+/// par l'allocateur global.
+/// Désactivé si la feature `oom_integration` est activée, et hors du noyau
+/// (compilation pour l'hôte de `host-tests/`).
+#[cfg(all(target_os = "none", not(feature = "oom_integration")))]
 #[alloc_error_handler]
 fn on_oom(layout: Layout) -> ! {
-    panic!("Out of memory: {:?}", layout);
+    match crate::ALLOCATOR.last_error() {
+        Some(error) => panic!("Out of memory: {:?}: {}", layout, error),
+        None => panic!("Out of memory: {:?}", layout),
+    }
 }
 
 
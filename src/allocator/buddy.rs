@@ -0,0 +1,453 @@
+//! Allocateur buddy binaire (feature `buddy`).
+//!
+//! Alternative à `SimpleAllocator` pour comparer fragmentation et débit sur
+//! les mêmes charges. Le heap est découpé en blocs de `MIN_BLOCK << order`
+//! octets, alignés sur leur taille ; une requête est arrondie à la puissance
+//! de deux supérieure, prise dans la liste libre de son ordre ou obtenue en
+//! scindant un bloc plus grand. À la libération, un bloc fusionne avec son
+//! « buddy » (adresse `addr ^ taille`) tant que celui-ci est libre.
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::ptr::{null_mut, NonNull};
+use spin::Once;
+
+use super::irq::IrqMutex;
+use super::shrink::{Registry, RegistryFull, Shrinker, ShrinkerStats, MAX_SHRINKERS};
+use super::{align_up, AllocError, HeapSnapshot, Usage, PAGE_SIZE};
+
+/// Taille du plus petit bloc (ordre 0).
+pub const MIN_BLOCK: usize = 16;
+/// Nombre d'ordres : le plus grand bloc fait `MIN_BLOCK << (ORDERS - 1)` (8 MiB).
+pub const ORDERS: usize = 20;
+
+/// En-tête d'un bloc libre, stocké au début du bloc.
+struct FreeBlock {
+    next: *mut FreeBlock,
+}
+
+/// Statistiques de l'allocateur buddy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BuddyStats {
+    /// Taille de la zone gérée (blocs de départ compris).
+    pub heap_size: usize,
+    /// Octets libres (somme des blocs libres).
+    pub free_bytes: usize,
+    /// Nombre de blocs libres par ordre.
+    pub free_blocks: [usize; ORDERS],
+    /// Nombre de blocs actuellement alloués.
+    pub in_use: usize,
+    /// Maximum atteint par `in_use`.
+    pub peak_in_use: usize,
+    /// Allocations refusées faute de bloc suffisant.
+    pub failed: usize,
+    /// Somme des tailles demandées des blocs alloués.
+    pub bytes_requested: usize,
+    /// Octets effectivement réservés (puissances de deux).
+    pub bytes_allocated: usize,
+}
+
+impl BuddyStats {
+    /// Taille du plus grand bloc libre, ou 0.
+    pub fn largest_free(&self) -> usize {
+        match self.free_blocks.iter().rposition(|&count| count > 0) {
+            Some(order) => block_size(order),
+            None => 0,
+        }
+    }
+
+    /// Affiche les statistiques sur le port série.
+    pub fn print(&self) {
+        crate::serial_println!(
+            "buddy heap: {} bytes, {} free, largest free block {}",
+            self.heap_size,
+            self.free_bytes,
+            self.largest_free()
+        );
+        crate::serial_println!(
+            "in_use {}, peak {}, failed {}, requested {}, allocated {}, waste {}",
+            self.in_use,
+            self.peak_in_use,
+            self.failed,
+            self.bytes_requested,
+            self.bytes_allocated,
+            self.bytes_allocated - self.bytes_requested
+        );
+        for (order, &count) in self.free_blocks.iter().enumerate().filter(|(_, &count)| count > 0) {
+            crate::serial_println!("{:>8} x {}", block_size(order), count);
+        }
+    }
+}
+
+/// Taille des blocs d'ordre `order`.
+const fn block_size(order: usize) -> usize {
+    MIN_BLOCK << order
+}
+
+/// Ordre du plus petit bloc couvrant `layout`, s'il en existe un.
+fn order_for(layout: &Layout) -> Option<usize> {
+    let size = layout.size().max(layout.align()).max(MIN_BLOCK).checked_next_power_of_two()?;
+    let order = (size / MIN_BLOCK).trailing_zeros() as usize;
+    if order < ORDERS {
+        Some(order)
+    } else {
+        None
+    }
+}
+
+/// Listes libres et compteurs, protégés par l'`IrqMutex` de `BuddyAllocator`.
+struct BuddyHeap {
+    /// Une liste libre par ordre.
+    free: [*mut FreeBlock; ORDERS],
+    /// Début de la zone gérée.
+    heap_start: usize,
+    /// Fin (exclue) de la zone gérée.
+    heap_end: usize,
+    stats: BuddyStats,
+}
+
+impl BuddyHeap {
+    const fn new() -> Self {
+        BuddyHeap {
+            free: [null_mut(); ORDERS],
+            heap_start: 0,
+            heap_end: 0,
+            stats: BuddyStats {
+                heap_size: 0,
+                free_bytes: 0,
+                free_blocks: [0; ORDERS],
+                in_use: 0,
+                peak_in_use: 0,
+                failed: 0,
+                bytes_requested: 0,
+                bytes_allocated: 0,
+            },
+        }
+    }
+
+    /// Découpe la zone en blocs aussi grands que possible, chacun aligné sur
+    /// sa taille.
+    unsafe fn init(&mut self, heap_start: usize, heap_size: usize) {
+        let start = align_up(heap_start, MIN_BLOCK);
+        let end = (heap_start + heap_size) & !(MIN_BLOCK - 1);
+        if end <= start {
+            return;
+        }
+        self.heap_start = start;
+        self.heap_end = end;
+        self.stats.heap_size = end - start;
+
+        let mut addr = start;
+        while addr < end {
+            let mut order = ORDERS - 1;
+            while !addr.is_multiple_of(block_size(order)) || addr + block_size(order) > end {
+                order -= 1;
+            }
+            self.push(addr, order);
+            addr += block_size(order);
+        }
+    }
+
+    /// Retire et retourne un bloc d'ordre `order`, en scindant au besoin un
+    /// bloc plus grand. Retourne `None` si aucun ne convient.
+    unsafe fn alloc_block(&mut self, order: usize) -> Option<usize> {
+        let found = (order..ORDERS).find(|&k| !self.free[k].is_null())?;
+        let addr = self.pop(found);
+        // Les moitiés hautes inutilisées retournent dans les listes libres.
+        for k in (order..found).rev() {
+            self.push(addr + block_size(k), k);
+        }
+        Some(addr)
+    }
+
+    /// Rend le bloc `addr` d'ordre `order` en le fusionnant avec ses buddies.
+    unsafe fn free_block(&mut self, mut addr: usize, mut order: usize) {
+        while order + 1 < ORDERS {
+            let buddy = addr ^ block_size(order);
+            if buddy < self.heap_start || buddy + block_size(order) > self.heap_end || !self.remove(buddy, order) {
+                break;
+            }
+            addr = addr.min(buddy);
+            order += 1;
+        }
+        self.push(addr, order);
+    }
+
+    /// Tente d'agrandir sur place le bloc `addr` de l'ordre `from` à l'ordre
+    /// `to` : chaque buddy supérieur doit être libre. Sans effet en cas d'échec.
+    unsafe fn grow_block(&mut self, addr: usize, from: usize, to: usize) -> bool {
+        let mergeable = (from..to).all(|k| {
+            let buddy = addr + block_size(k);
+            addr.is_multiple_of(block_size(k + 1)) && buddy + block_size(k) <= self.heap_end && self.contains_free(buddy, k)
+        });
+        if mergeable {
+            for k in from..to {
+                self.remove(addr + block_size(k), k);
+            }
+        }
+        mergeable
+    }
+
+    /// Réduit sur place le bloc `addr` de l'ordre `from` à l'ordre `to`.
+    unsafe fn shrink_block(&mut self, addr: usize, from: usize, to: usize) {
+        for k in (to..from).rev() {
+            self.push(addr + block_size(k), k);
+        }
+    }
+
+    unsafe fn push(&mut self, addr: usize, order: usize) {
+        let block = addr as *mut FreeBlock;
+        block.write(FreeBlock { next: self.free[order] });
+        self.free[order] = block;
+        self.stats.free_blocks[order] += 1;
+        self.stats.free_bytes += block_size(order);
+    }
+
+    unsafe fn pop(&mut self, order: usize) -> usize {
+        let block = self.free[order];
+        self.free[order] = (*block).next;
+        self.stats.free_blocks[order] -= 1;
+        self.stats.free_bytes -= block_size(order);
+        block as usize
+    }
+
+    /// Retire `addr` de la liste libre d'ordre `order` s'il s'y trouve.
+    unsafe fn remove(&mut self, addr: usize, order: usize) -> bool {
+        let mut link: *mut *mut FreeBlock = &mut self.free[order];
+        while !(*link).is_null() {
+            if *link as usize == addr {
+                *link = (**link).next;
+                self.stats.free_blocks[order] -= 1;
+                self.stats.free_bytes -= block_size(order);
+                return true;
+            }
+            link = &mut (**link).next;
+        }
+        false
+    }
+
+    /// Indique si `addr` est dans la liste libre d'ordre `order`.
+    unsafe fn contains_free(&self, addr: usize, order: usize) -> bool {
+        let mut block = self.free[order];
+        while !block.is_null() {
+            if block as usize == addr {
+                return true;
+            }
+            block = (*block).next;
+        }
+        false
+    }
+}
+
+/// Allocateur global buddy, interchangeable avec `SimpleAllocator`.
+pub struct BuddyAllocator {
+    heap: IrqMutex<BuddyHeap>,
+    /// Garde d'initialisation unique du heap (`init` ou heap statique).
+    heap_ready: Once<()>,
+    /// Callbacks de récupération appelés avant de déclarer l'OOM.
+    shrinkers: Registry,
+    /// Raison du dernier échec d'allocation.
+    last_error: IrqMutex<Option<AllocError>>,
+}
+
+unsafe impl Sync for BuddyAllocator {}
+unsafe impl Send for BuddyAllocator {}
+
+impl Default for BuddyAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BuddyAllocator {
+    /// Construit un allocateur sans heap.
+    pub const fn new() -> Self {
+        BuddyAllocator {
+            heap: IrqMutex::new(BuddyHeap::new()),
+            heap_ready: Once::new(),
+            shrinkers: Registry::new(),
+            last_error: IrqMutex::new(None),
+        }
+    }
+
+    /// Initialise l'allocateur sur la région `heap_start..heap_start + heap_size`.
+    ///
+    /// Même contrat que `SimpleAllocator::init` : seul le premier appel est
+    /// pris en compte.
+    ///
+    /// # Safety
+    /// Caller must guarantee that `heap_start..heap_start + heap_size`
+    /// is valid RAM and not used elsewhere, before any allocation occurs.
+    pub unsafe fn init(&self, heap_start: usize, heap_size: usize) {
+        self.heap_ready.call_once(|| self.heap.lock().init(heap_start, heap_size));
+    }
+
+    /// Alloue `count` pages contiguës alignées sur `PAGE_SIZE` (arrondies à
+    /// une puissance de deux).
+    ///
+    /// # Safety
+    /// Le bloc doit être rendu par `dealloc_pages` avec le même `count`.
+    pub unsafe fn alloc_pages(&self, count: usize) -> *mut u8 {
+        match Self::page_layout(count) {
+            Some(layout) => self.alloc(layout),
+            None => null_mut(),
+        }
+    }
+
+    /// Rend `count` pages obtenues par `alloc_pages`.
+    ///
+    /// # Safety
+    /// `ptr` et `count` doivent correspondre à un appel antérieur à `alloc_pages`.
+    pub unsafe fn dealloc_pages(&self, ptr: *mut u8, count: usize) {
+        if let Some(layout) = Self::page_layout(count) {
+            self.dealloc(ptr, layout);
+        }
+    }
+
+    /// Retourne un instantané des statistiques.
+    pub fn stats(&self) -> BuddyStats {
+        self.heap.lock().stats
+    }
+
+    /// Affiche les statistiques courantes sur le port série.
+    pub fn print_stats(&self) {
+        self.stats().print();
+    }
+
+    /// Relève les blocs vivants, pour `HeapSnapshot::leaks_since`.
+    ///
+    /// Le buddy n'a pas de classes de slab : tous ses blocs sont comptés
+    /// dans `large`.
+    pub fn snapshot(&self) -> HeapSnapshot {
+        let stats = self.stats();
+        HeapSnapshot {
+            large: Usage { blocks: stats.in_use, bytes: stats.bytes_requested },
+            ..HeapSnapshot::default()
+        }
+    }
+
+    /// Enregistre un shrinker (voir `SimpleAllocator::register_shrinker`).
+    pub fn register_shrinker(&self, shrinker: &'static Shrinker) -> Result<(), RegistryFull> {
+        self.shrinkers.register(shrinker)
+    }
+
+    /// Compteurs des shrinkers enregistrés, dans l'ordre d'enregistrement.
+    pub fn shrinker_stats(&self) -> [Option<ShrinkerStats>; MAX_SHRINKERS] {
+        self.shrinkers.stats()
+    }
+
+    /// Alloue un bloc pour `layout` (voir `SimpleAllocator::try_alloc`).
+    pub fn try_alloc(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        if layout.align() > PAGE_SIZE {
+            return Err(self.fail(AllocError::Misaligned));
+        }
+        // Interruptions masquées, comme dans `SimpleAllocator`.
+        #[cfg(feature = "static_heap")]
+        if self.heap_ready.r#try().is_none() {
+            super::irq::without_interrupts(|| {
+                self.heap_ready.call_once(|| unsafe {
+                    self.heap.lock().init(core::ptr::addr_of_mut!(super::HEAP.0) as usize, super::HEAP_SIZE)
+                })
+            });
+        }
+        loop {
+            match self.alloc_block(&layout) {
+                Ok(ptr) => return Ok(ptr),
+                Err(error) if !self.shrinkers.reclaim(&layout) => return Err(self.fail(error)),
+                Err(_) => {}
+            }
+        }
+    }
+
+    /// Raison du dernier échec d'allocation, s'il y en a eu un.
+    pub fn last_error(&self) -> Option<AllocError> {
+        *self.last_error.lock()
+    }
+
+    /// Retient `error` pour `last_error`.
+    fn fail(&self, error: AllocError) -> AllocError {
+        *self.last_error.lock() = Some(error);
+        error
+    }
+
+    /// Prend un bloc pour `layout` et met les compteurs à jour.
+    fn alloc_block(&self, layout: &Layout) -> Result<NonNull<u8>, AllocError> {
+        let mut heap = self.heap.lock();
+        let order = order_for(layout).filter(|&order| block_size(order) <= heap.stats.heap_size);
+        // SAFETY: les listes libres ne contiennent que des blocs de la zone gérée.
+        let (order, addr) = match order.map(|order| (order, unsafe { heap.alloc_block(order) })) {
+            Some((order, Some(addr))) => (order, addr),
+            failed => {
+                heap.stats.failed += 1;
+                return Err(match failed {
+                    Some((order, _)) => AllocError::Exhausted(Some(block_size(order))),
+                    None => AllocError::TooLarge,
+                });
+            }
+        };
+        let stats = &mut heap.stats;
+        stats.in_use += 1;
+        stats.peak_in_use = stats.peak_in_use.max(stats.in_use);
+        stats.bytes_requested += layout.size();
+        stats.bytes_allocated += block_size(order);
+        // SAFETY: aucun bloc ne commence à l'adresse 0.
+        Ok(unsafe { NonNull::new_unchecked(addr as *mut u8) })
+    }
+
+    fn page_layout(count: usize) -> Option<Layout> {
+        let size = count.checked_mul(PAGE_SIZE).filter(|&size| size > 0)?;
+        Layout::from_size_align(size, PAGE_SIZE).ok()
+    }
+}
+
+unsafe impl GlobalAlloc for BuddyAllocator {
+    /// Alloue le plus petit bloc couvrant la taille et l'alignement de
+    /// `layout`, en faisant appel aux shrinkers si le heap est épuisé.
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.try_alloc(layout).map_or(null_mut(), NonNull::as_ptr)
+    }
+
+    /// Rend le bloc et le fusionne avec ses buddies libres.
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let order = match order_for(&layout) {
+            Some(order) => order,
+            None => return,
+        };
+        let mut heap = self.heap.lock();
+        heap.free_block(ptr as usize, order);
+        heap.stats.in_use -= 1;
+        heap.stats.bytes_requested -= layout.size();
+        heap.stats.bytes_allocated -= block_size(order);
+    }
+
+    /// Redimensionne sur place quand l'ordre ne change pas, quand il diminue
+    /// (les moitiés hautes sont rendues) ou quand les buddies supérieurs sont
+    /// libres ; sinon le bloc est déplacé.
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        if let (Some(old), Some(new)) = (order_for(&layout), order_for(&new_layout)) {
+            let mut heap = self.heap.lock();
+            let addr = ptr as usize;
+            let in_place = match new.cmp(&old) {
+                core::cmp::Ordering::Equal => true,
+                core::cmp::Ordering::Less => {
+                    heap.shrink_block(addr, old, new);
+                    true
+                }
+                core::cmp::Ordering::Greater => heap.grow_block(addr, old, new),
+            };
+            if in_place {
+                let stats = &mut heap.stats;
+                stats.bytes_requested = stats.bytes_requested - layout.size() + new_size;
+                stats.bytes_allocated = stats.bytes_allocated - block_size(old) + block_size(new);
+                return ptr;
+            }
+        }
+
+        let new_ptr = self.alloc(new_layout);
+        if !new_ptr.is_null() {
+            core::ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+            self.dealloc(ptr, layout);
+        }
+        new_ptr
+    }
+}
@@ -0,0 +1,186 @@
+//! Caches d'objets typés, à la manière des `kmem_cache` de Linux.
+//!
+//! Un `ObjectCache<T>` possède son propre slab, dimensionné pour `T` et
+//! indépendant des classes de taille de l'allocateur global : un sous-système
+//! peut ainsi réserver un cache nommé à ses objets fréquents (entrées de
+//! répertoire, descripteurs de tâche…) et suivre ses statistiques à part.
+//! Les pages du slab sont demandées à l'allocateur global, et toutes rendues
+//! quand le cache est détruit.
+
+use core::alloc::Layout;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem::{align_of, size_of};
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+
+use super::irq::IrqMutex;
+use super::{align_up, Page, Slab, SlabStats, DEFAULT_CLASSES, PAGE_SIZE, REDZONE};
+
+/// Cache nommé d'objets de type `T`.
+///
+/// ```ignore
+/// static TASKS: ObjectCache<Task> = ObjectCache::new("task", Task::new);
+/// let task = TASKS.alloc().expect("out of memory");
+/// ```
+pub struct ObjectCache<T> {
+    /// Nom du cache, repris par `Debug`.
+    name: &'static str,
+    slab: IrqMutex<Slab>,
+    /// Construit un objet neuf pour `alloc`.
+    ctor: fn() -> T,
+    /// Appelé sur l'objet avant sa destruction et le retour de son bloc.
+    dtor: Option<fn(&mut T)>,
+    _marker: PhantomData<T>,
+}
+
+// SAFETY: le slab n'est manipulé que sous son `IrqMutex`, et les objets `T`
+// peuvent être construits et détruits depuis n'importe quel cœur.
+unsafe impl<T: Send> Sync for ObjectCache<T> {}
+unsafe impl<T: Send> Send for ObjectCache<T> {}
+
+impl<T> ObjectCache<T> {
+    /// Crée un cache vide ; `ctor` construit les objets servis par `alloc`.
+    ///
+    /// Échoue à la compilation (ou panique) si `T` ne tient pas dans une page.
+    pub const fn new(name: &'static str, ctor: fn() -> T) -> Self {
+        let link = size_of::<*mut u8>();
+        let align = if align_of::<T>() > link { align_of::<T>() } else { link };
+        let min = DEFAULT_CLASSES[0].size;
+        let size = if size_of::<T>() + REDZONE > min { size_of::<T>() + REDZONE } else { min };
+        ObjectCache {
+            name,
+            slab: IrqMutex::new(Slab::with_align(align_up(size, align), align)),
+            ctor,
+            dtor: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Ajoute un destructeur, appelé avant `drop` quand un objet est rendu.
+    pub const fn with_destructor(mut self, dtor: fn(&mut T)) -> Self {
+        self.dtor = Some(dtor);
+        self
+    }
+
+    /// Nom du cache.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Alloue un objet construit par le constructeur du cache.
+    ///
+    /// Retourne `None` si aucune page ne peut être obtenue.
+    pub fn alloc(&self) -> Option<CacheBox<'_, T>> {
+        self.alloc_with((self.ctor)())
+    }
+
+    /// Place `value` dans un bloc du cache, sans passer par le constructeur.
+    ///
+    /// Retourne `None` (et détruit `value`) si aucune page ne peut être obtenue.
+    pub fn alloc_with(&self, value: T) -> Option<CacheBox<'_, T>> {
+        // SAFETY: `grow` fournit des pages alignées réservées au slab, et le
+        // bloc obtenu est dimensionné et aligné pour `T`.
+        unsafe {
+            let (block, _) = self.slab.lock().alloc(Layout::new::<T>(), Self::grow);
+            let ptr = NonNull::new(block as *mut T)?;
+            ptr.as_ptr().write(value);
+            Some(CacheBox { ptr, cache: self })
+        }
+    }
+
+    /// Retourne les statistiques du slab de ce cache.
+    pub fn stats(&self) -> SlabStats {
+        self.slab.lock().stats()
+    }
+
+    /// Détruit l'objet `ptr` et rend son bloc au slab.
+    ///
+    /// # Safety
+    /// `ptr` doit provenir de ce cache et ne plus être utilisé.
+    unsafe fn free(&self, ptr: NonNull<T>) {
+        if let Some(dtor) = self.dtor {
+            dtor(&mut *ptr.as_ptr());
+        }
+        ptr.as_ptr().drop_in_place();
+        self.slab.lock().dealloc(ptr.as_ptr() as *mut u8, Layout::new::<T>(), Self::release);
+    }
+
+    /// Demande une page à l'allocateur global.
+    fn grow() -> Option<Page> {
+        // SAFETY: `page_layout` a une taille non nulle.
+        let addr = unsafe { alloc::alloc::alloc(Self::page_layout()) };
+        if addr.is_null() {
+            return None;
+        }
+        Some(Page { addr, zeroed: false })
+    }
+
+    /// Rend une page vide à l'allocateur global.
+    fn release(page: *mut u8) {
+        // SAFETY: `page` a été obtenue par `grow` avec le même layout.
+        unsafe { alloc::alloc::dealloc(page, Self::page_layout()) }
+    }
+
+    fn page_layout() -> Layout {
+        // SAFETY: `PAGE_SIZE` est une puissance de deux non nulle.
+        unsafe { Layout::from_size_align_unchecked(PAGE_SIZE, PAGE_SIZE) }
+    }
+}
+
+impl<T> Drop for ObjectCache<T> {
+    /// Rend toutes les pages du cache à l'allocateur global. Aucune
+    /// `CacheBox` ne survit au cache qu'elle emprunte : ses blocs sont libres.
+    fn drop(&mut self) {
+        // SAFETY: plus aucun objet du cache n'est accessible, et les pages
+        // viennent de `grow`.
+        unsafe { self.slab.lock().release_pages(Self::release) }
+    }
+}
+
+impl<T> fmt::Debug for ObjectCache<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ObjectCache")
+            .field("name", &self.name)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+/// Objet alloué dans un `ObjectCache`, rendu au cache quand il est abandonné.
+pub struct CacheBox<'a, T> {
+    ptr: NonNull<T>,
+    cache: &'a ObjectCache<T>,
+}
+
+impl<T> Deref for CacheBox<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: `ptr` désigne un objet initialisé possédé par cette boîte.
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T> DerefMut for CacheBox<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: idem, et l'accès est exclusif via `&mut self`.
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl<T> Drop for CacheBox<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: l'objet provient de `self.cache` et n'est plus accessible.
+        unsafe { self.cache.free(self.ptr) }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for CacheBox<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+unsafe impl<T: Send> Send for CacheBox<'_, T> {}
+unsafe impl<T: Sync> Sync for CacheBox<'_, T> {}
@@ -0,0 +1,67 @@
+//! Identité du processeur courant.
+//!
+//! Partagée par les structures tenues par CPU (magazines, garde de
+//! récupération des shrinkers). `init_cpu` fait pointer la base GS de chaque
+//! CPU vers son index, que `current` relit par `gs:[0]`. La base GS est
+//! propre à chaque CPU : tant qu'elle est nulle (CPU pas encore enregistré),
+//! `current` retourne 0 sans la déréférencer. Hors du noyau (tests sur
+//! l'hôte), tout s'exécute comme sur le CPU 0.
+
+/// Nombre de CPU suivis individuellement.
+pub const MAX_CPUS: usize = 8;
+
+/// Index de chaque CPU, pointé par sa base GS ; la dernière case est partagée
+/// par les CPU au-delà de `MAX_CPUS`.
+#[cfg(target_os = "none")]
+static INDICES: [usize; MAX_CPUS + 1] = {
+    let mut indices = [0; MAX_CPUS + 1];
+    let mut i = 0;
+    while i <= MAX_CPUS {
+        indices[i] = i;
+        i += 1;
+    }
+    indices
+};
+
+/// Enregistre `index` comme identifiant du CPU courant. À appeler sur chaque
+/// CPU avant sa première allocation ; `init_heap` le fait pour le CPU de
+/// démarrage (index 0).
+///
+/// # Safety
+/// La base GS du CPU courant ne doit servir à rien d'autre.
+#[cfg(target_os = "none")]
+pub unsafe fn init_cpu(index: usize) {
+    use x86_64::registers::model_specific::GsBase;
+    use x86_64::VirtAddr;
+
+    GsBase::write(VirtAddr::from_ptr(&INDICES[index.min(MAX_CPUS)]));
+}
+
+/// Sans effet hors du noyau.
+///
+/// # Safety
+/// Aucune exigence sur l'hôte.
+#[cfg(not(target_os = "none"))]
+pub unsafe fn init_cpu(_index: usize) {}
+
+/// Identifiant du CPU courant, enregistré par `init_cpu` (0 avant).
+#[cfg(target_os = "none")]
+pub fn current() -> usize {
+    use x86_64::registers::model_specific::GsBase;
+
+    if GsBase::read().is_null() {
+        return 0;
+    }
+    let index: usize;
+    // SAFETY: une base GS non nulle pointe vers une case de `INDICES` (`init_cpu`).
+    unsafe {
+        core::arch::asm!("mov {}, gs:[0]", out(reg) index, options(nostack, preserves_flags, readonly));
+    }
+    index
+}
+
+/// Identifiant du CPU courant : toujours 0 hors du noyau.
+#[cfg(not(target_os = "none"))]
+pub fn current() -> usize {
+    0
+}
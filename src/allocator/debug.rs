@@ -0,0 +1,165 @@
+//! Mode durci du slab allocator (feature `slab_debug`).
+//!
+//! Chaque page de slab porte un numéro magique et un bitmap des blocs alloués.
+//! Les blocs libérés sont empoisonnés, et l'espace entre la fin de l'objet et
+//! la fin du bloc sert de zone rouge (canari). Toute incohérence détectée lors
+//! d'une allocation ou d'une libération provoque un panic détaillé.
+
+use core::alloc::Layout;
+use core::mem::size_of;
+
+use super::PAGE_SIZE;
+
+/// Valeur écrite dans l'en-tête de chaque page de slab.
+pub const PAGE_MAGIC: usize = 0x51AB_51AB_51AB_51AB;
+/// Motif des octets d'un bloc libéré (après le lien de la liste libre).
+pub const POISON: u8 = 0x6B;
+/// Motif de la zone rouge placée après chaque objet alloué.
+pub const CANARY: u8 = 0xCA;
+/// Taille minimale de la zone rouge réservée après chaque objet.
+pub const REDZONE: usize = 8;
+/// Nombre maximal de blocs par page suivis par le bitmap.
+pub const MAX_BLOCKS: usize = 256;
+
+/// Bitmap des blocs alloués d'une page.
+pub type Bitmap = [u64; MAX_BLOCKS / 64];
+
+/// Informations de contrôle stockées dans l'en-tête d'une page.
+#[repr(C)]
+pub struct PageGuard {
+    /// `PAGE_MAGIC` si la page appartient à un slab.
+    magic: usize,
+    /// Taille des blocs du slab propriétaire.
+    obj_size: usize,
+    /// Un bit par bloc, à 1 si le bloc est alloué.
+    allocated: Bitmap,
+}
+
+impl PageGuard {
+    /// Garde d'une page neuve dont aucun bloc n'est alloué.
+    pub fn new(obj_size: usize) -> Self {
+        PageGuard { magic: PAGE_MAGIC, obj_size, allocated: [0; MAX_BLOCKS / 64] }
+    }
+}
+
+/// Efface le numéro magique d'une page rendue par son slab : un pointeur
+/// périmé vers cette page n'y est plus pris pour un bloc de slab.
+///
+/// # Safety
+/// `guard` doit pointer vers la garde de la page, encore réservée au slab.
+pub unsafe fn on_release(guard: *mut PageGuard) {
+    (*guard).magic = 0;
+}
+
+/// Géométrie d'un slab nécessaire aux vérifications.
+#[derive(Clone, Copy)]
+pub struct Geometry {
+    pub obj_size: usize,
+    pub first_block: usize,
+    pub capacity: usize,
+}
+
+/// Empoisonne un bloc libre, en préservant son lien de liste libre.
+///
+/// # Safety
+/// `block` doit pointer vers un bloc de `obj_size` octets.
+pub unsafe fn poison(block: *mut u8, obj_size: usize) {
+    let link = size_of::<*mut u8>();
+    block.add(link).write_bytes(POISON, obj_size - link);
+}
+
+/// Vérifie un bloc qui vient d'être distribué, puis le marque alloué et écrit
+/// sa zone rouge. Le poison n'est contrôlé que pour un bloc `recycled`, repris
+/// dans la liste libre (un bloc neuf n'a jamais été empoisonné).
+///
+/// # Safety
+/// `guard` doit être la garde de la page contenant `block`.
+pub unsafe fn on_alloc(guard: &mut PageGuard, geo: Geometry, block: *mut u8, recycled: bool, layout: &Layout) {
+    let index = block_index(geo, block);
+    if test_bit(&guard.allocated, index) {
+        panic!("slab_debug: free list corrupted, block {:p} already allocated ({:?})", block, layout);
+    }
+    let link = if recycled { size_of::<*mut u8>() } else { geo.obj_size };
+    for offset in link..geo.obj_size {
+        if *block.add(offset) != POISON {
+            panic!(
+                "slab_debug: use after free, block {:p} modified at offset {} while free ({:?})",
+                block, offset, layout
+            );
+        }
+    }
+    set_bit(&mut guard.allocated, index, true);
+    block.add(layout.size()).write_bytes(CANARY, geo.obj_size - layout.size());
+}
+
+/// Vérifie qu'un bloc peut être libéré (voir `check_live`), puis le marque
+/// libre et l'empoisonne.
+///
+/// # Safety
+/// `guard` doit pointer vers la garde (lisible) de la page contenant `ptr`.
+pub unsafe fn on_dealloc(guard: *mut PageGuard, geo: Geometry, ptr: *mut u8, layout: &Layout) {
+    let index = check_live(guard, geo, ptr, layout, "free");
+    set_bit(&mut (*guard).allocated, index, false);
+    poison(ptr, geo.obj_size);
+}
+
+/// Vérifie un bloc redimensionné sur place (voir `check_live`), puis déplace
+/// sa zone rouge à la fin de la nouvelle taille.
+///
+/// # Safety
+/// `guard` doit pointer vers la garde (lisible) de la page contenant `ptr`,
+/// et `new_size` ne doit pas dépasser la taille des blocs.
+pub unsafe fn on_resize(guard: *mut PageGuard, geo: Geometry, ptr: *mut u8, layout: &Layout, new_size: usize) {
+    check_live(guard, geo, ptr, layout, "realloc");
+    ptr.add(new_size).write_bytes(CANARY, geo.obj_size - new_size);
+}
+
+/// Vérifie qu'un bloc est vivant : page de slab valide, adresse de bloc
+/// exacte, bloc alloué et zone rouge intacte. Retourne l'indice du bloc.
+unsafe fn check_live(guard: *mut PageGuard, geo: Geometry, ptr: *mut u8, layout: &Layout, op: &str) -> usize {
+    let page = ptr as usize & !(PAGE_SIZE - 1);
+    if (*guard).magic != PAGE_MAGIC || (*guard).obj_size != geo.obj_size {
+        panic!(
+            "slab_debug: invalid {} of {:p} ({:?}): not in a {}-byte slab page",
+            op, ptr, layout, geo.obj_size
+        );
+    }
+    let offset = (ptr as usize - page).wrapping_sub(geo.first_block);
+    if !offset.is_multiple_of(geo.obj_size) || offset / geo.obj_size >= geo.capacity {
+        panic!("slab_debug: invalid {} of {:p} ({:?}): not a block boundary", op, ptr, layout);
+    }
+    let index = offset / geo.obj_size;
+    if !test_bit(&(*guard).allocated, index) {
+        if op == "free" {
+            panic!("slab_debug: double free of {:p} ({:?})", ptr, layout);
+        }
+        panic!("slab_debug: {} of freed block {:p} ({:?})", op, ptr, layout);
+    }
+    for offset in layout.size()..geo.obj_size {
+        let byte = *ptr.add(offset);
+        if byte != CANARY {
+            panic!(
+                "slab_debug: red zone of {:p} ({:?}) overwritten at offset {} (found {:#04x})",
+                ptr, layout, offset, byte
+            );
+        }
+    }
+    index
+}
+
+/// Indice du bloc `block` dans sa page.
+fn block_index(geo: Geometry, block: *mut u8) -> usize {
+    (block as usize & (PAGE_SIZE - 1)).wrapping_sub(geo.first_block) / geo.obj_size
+}
+
+fn test_bit(bitmap: &Bitmap, index: usize) -> bool {
+    bitmap[index / 64] & (1 << (index % 64)) != 0
+}
+
+fn set_bit(bitmap: &mut Bitmap, index: usize, value: bool) {
+    if value {
+        bitmap[index / 64] |= 1 << (index % 64);
+    } else {
+        bitmap[index / 64] &= !(1 << (index % 64));
+    }
+}
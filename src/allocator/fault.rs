@@ -0,0 +1,62 @@
+//! Injection de pannes d'allocation (feature `fault_injection`).
+//!
+//! Une fois armé par `SimpleAllocator::inject_faults`, l'allocateur refuse
+//! certaines allocations comme s'il manquait de mémoire, afin de faire passer
+//! le code appelant par ses chemins d'erreur dans les tests.
+
+/// Allocations à faire échouer ; les compteurs partent de l'armement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// Seule la `n`-ième allocation échoue (1 = la prochaine).
+    Nth(usize),
+    /// Une allocation sur `n` échoue (la `n`-ième, la `2n`-ième…).
+    Every(usize),
+    /// Les allocations servies par le slab de blocs de `size` octets échouent.
+    Class(usize),
+    /// Les allocations servies par l'allocateur de gros objets échouent.
+    Large,
+}
+
+/// État de l'injection.
+pub struct Injector {
+    fault: Option<Fault>,
+    /// Allocations vues depuis l'armement.
+    seen: usize,
+    /// Allocations refusées depuis l'armement.
+    injected: usize,
+}
+
+impl Injector {
+    /// Crée un injecteur désarmé.
+    pub const fn new() -> Self {
+        Injector { fault: None, seen: 0, injected: 0 }
+    }
+
+    /// Arme (`Some`) ou désarme (`None`) l'injecteur et remet ses compteurs à
+    /// zéro. Retourne le nombre de pannes injectées depuis l'armement précédent.
+    pub fn arm(&mut self, fault: Option<Fault>) -> usize {
+        let injected = self.injected;
+        *self = Injector { fault, seen: 0, injected: 0 };
+        injected
+    }
+
+    /// Indique si une allocation doit échouer ; `class` est la taille des
+    /// blocs du slab qui la sert, `None` pour un gros objet.
+    pub fn should_fail(&mut self, class: Option<usize>) -> bool {
+        let fault = match self.fault {
+            Some(fault) => fault,
+            None => return false,
+        };
+        self.seen += 1;
+        let fail = match fault {
+            Fault::Nth(n) => self.seen == n,
+            Fault::Every(n) => n > 0 && self.seen.is_multiple_of(n),
+            Fault::Class(size) => class == Some(size),
+            Fault::Large => class.is_none(),
+        };
+        if fail {
+            self.injected += 1;
+        }
+        fail
+    }
+}
@@ -0,0 +1,35 @@
+//! Remontée de pile par la chaîne des frame pointers (features `alloc_trace`
+//! et `kasan`).
+//!
+//! Les frame pointers sont conservés par `.cargo/config.toml` : chaque frame
+//! commence par le couple (rbp de l'appelant, adresse de retour).
+
+/// Relève les `N` premières adresses de retour de la pile.
+///
+/// Doit être inliné dans une fonction `#[inline(never)]` appelée directement
+/// par l'allocateur : le premier frame (celui de l'allocateur) est sauté, la
+/// première adresse est donc dans l'appelant de l'allocateur. La remontée
+/// s'arrête sur un frame nul, mal aligné ou qui ne remonte pas la pile.
+#[inline(always)]
+pub fn callers<const N: usize>() -> [usize; N] {
+    let mut callers = [0; N];
+    let mut frame: usize;
+    // SAFETY: lecture du registre rbp uniquement.
+    unsafe { core::arch::asm!("mov {}, rbp", out(reg) frame, options(nomem, nostack, preserves_flags)) };
+    for slot in 0..=N {
+        if frame == 0 || !frame.is_multiple_of(8) {
+            break;
+        }
+        // SAFETY: avec les frame pointers forcés, `frame` pointe vers le couple
+        // (rbp de l'appelant, adresse de retour) sauvegardé sur la pile.
+        let (next, ret) = unsafe { (*(frame as *const usize), *((frame + 8) as *const usize)) };
+        if slot > 0 {
+            callers[slot - 1] = ret;
+        }
+        if next <= frame {
+            break;
+        }
+        frame = next;
+    }
+    callers
+}
@@ -0,0 +1,102 @@
+//! Verrous de l'allocateur, pris interruptions masquées.
+//!
+//! Un gestionnaire d'interruption qui alloue ne doit pas attendre un verrou
+//! tenu par le code qu'il interrompt : sur ce CPU, ce verrou ne serait jamais
+//! rendu. `IrqMutex` masque donc les interruptions du CPU courant avant de
+//! prendre son verrou, et ne les rétablit qu'en le rendant, si elles étaient
+//! actives. Les gardes imbriqués se rendent dans l'ordre inverse de leur
+//! prise ; seul le plus extérieur réactive les interruptions.
+//!
+//! Les exceptions (`int3`, défaut de page) ne sont pas masquables : leurs
+//! gestionnaires ne sont sûrs que s'ils ne surviennent pas sous un verrou
+//! de l'allocateur. Hors du noyau (tests sur l'hôte), seul le verrou est pris.
+
+use core::mem::ManuallyDrop;
+use core::ops::{Deref, DerefMut};
+use spin::{Mutex, MutexGuard};
+
+/// `spin::Mutex` tenu interruptions masquées.
+pub struct IrqMutex<T> {
+    inner: Mutex<T>,
+}
+
+/// Garde de `IrqMutex` : rend le verrou, puis rétablit les interruptions.
+pub struct IrqMutexGuard<'a, T> {
+    guard: ManuallyDrop<MutexGuard<'a, T>>,
+    /// Interruptions actives avant la prise du verrou.
+    enabled: bool,
+}
+
+impl<T> IrqMutex<T> {
+    /// Crée un verrou libre autour de `value`.
+    pub const fn new(value: T) -> Self {
+        IrqMutex { inner: Mutex::new(value) }
+    }
+
+    /// Masque les interruptions du CPU courant et prend le verrou.
+    pub fn lock(&self) -> IrqMutexGuard<'_, T> {
+        let enabled = disable();
+        IrqMutexGuard { guard: ManuallyDrop::new(self.inner.lock()), enabled }
+    }
+}
+
+impl<T> Deref for IrqMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for IrqMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T> Drop for IrqMutexGuard<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: le garde n'est plus utilisé après cette ligne.
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+        restore(self.enabled);
+    }
+}
+
+/// Exécute `f` interruptions masquées.
+#[cfg(any(feature = "magazines", feature = "static_heap"))]
+pub fn without_interrupts<R>(f: impl FnOnce() -> R) -> R {
+    let enabled = disable();
+    let result = f();
+    restore(enabled);
+    result
+}
+
+/// Masque les interruptions et indique si elles étaient actives.
+#[cfg(target_os = "none")]
+fn disable() -> bool {
+    use x86_64::instructions::interrupts;
+
+    let enabled = interrupts::are_enabled();
+    if enabled {
+        interrupts::disable();
+    }
+    enabled
+}
+
+/// Pas d'interruptions à masquer hors du noyau.
+#[cfg(not(target_os = "none"))]
+fn disable() -> bool {
+    false
+}
+
+/// Réactive les interruptions si `disable` les a trouvées actives.
+#[cfg(target_os = "none")]
+fn restore(enabled: bool) {
+    if enabled {
+        x86_64::instructions::interrupts::enable();
+    }
+}
+
+/// Pas d'interruptions à rétablir hors du noyau.
+#[cfg(not(target_os = "none"))]
+fn restore(_enabled: bool) {}
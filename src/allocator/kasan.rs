@@ -0,0 +1,318 @@
+//! Mémoire fantôme du heap, à la manière de KASAN (feature `kasan`).
+//!
+//! Un octet fantôme décrit chaque granule de `GRANULE` octets du heap : `0`
+//! si tout le granule est accessible, `k` (1 à 7) si seuls ses `k` premiers
+//! octets le sont, sinon la raison de l'interdiction (`UNALLOCATED`,
+//! `REDZONE`, `FREED`). La mémoire fantôme est prise à la fin du heap confié
+//! à `init` (un neuvième de sa taille).
+//!
+//! Rien n'est instrumenté par le compilateur : le code qui manipule des
+//! pointeurs bruts (tampons FAT32, `from_raw_parts`…) appelle
+//! `SimpleAllocator::check_read` / `check_write` avant l'accès. Une violation
+//! est signalée sur la série avec l'adresse, la taille, l'historique du bloc
+//! concerné et les octets fantômes voisins :
+//!
+//! ```text
+//! kasan: use-after-free write of 4 bytes at 0x444400001040 (first bad byte 0x444400001040)
+//! kasan: block 0x444400001040: 24 bytes requested, 32 reserved
+//! kasan: allocated by 0x2011a3,0x20455e,0x2088f0,0x201016
+//! kasan: freed by 0x2011f7,0x20455e,0x2088f0,0x201016
+//! kasan: shadow 0x444400001020: 00 00 03 fc [fb] fb fb fb fc
+//! ```
+//!
+//! Les blocs libérés sont réutilisables aussitôt (pas de quarantaine) : un
+//! accès par un pointeur périmé vers un bloc réalloué n'est pas détecté.
+
+use core::fmt;
+
+use super::{align_up, frames};
+
+/// Octets du heap décrits par un octet fantôme.
+pub const GRANULE: usize = 8;
+/// Nombre de blocs dont l'historique est conservé pour les rapports.
+pub const KASAN_RECORDS: usize = 128;
+/// Adresses de retour relevées à l'allocation et à la libération.
+const DEPTH: usize = 4;
+
+/// Heap jamais distribué (ou interne à l'allocateur).
+const UNALLOCATED: u8 = 0xFF;
+/// Fin d'un bloc au-delà de la taille demandée.
+const REDZONE: u8 = 0xFC;
+/// Bloc libéré.
+const FREED: u8 = 0xFB;
+
+/// Nature d'un accès vérifié.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// Raison d'une violation, d'après l'octet fantôme du premier octet fautif.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// Au-delà de la taille demandée d'un bloc.
+    OutOfBounds,
+    /// Dans un bloc libéré.
+    UseAfterFree,
+    /// Dans une partie du heap qui n'a jamais été distribuée.
+    Unallocated,
+}
+
+/// Accès invalide détecté par `check_read` ou `check_write`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Violation {
+    pub kind: ViolationKind,
+    pub access: Access,
+    /// Début de l'accès.
+    pub addr: usize,
+    /// Taille de l'accès.
+    pub size: usize,
+    /// Premier octet inaccessible.
+    pub bad_addr: usize,
+}
+
+impl fmt::Display for Violation {
+    /// Par exemple `heap-out-of-bounds read of 8 bytes at 0x… (first bad byte 0x…)`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            ViolationKind::OutOfBounds => "heap-out-of-bounds",
+            ViolationKind::UseAfterFree => "use-after-free",
+            ViolationKind::Unallocated => "unallocated",
+        };
+        let access = match self.access {
+            Access::Read => "read",
+            Access::Write => "write",
+        };
+        write!(
+            f,
+            "{} {} of {} bytes at {:#x} (first bad byte {:#x})",
+            kind, access, self.size, self.addr, self.bad_addr
+        )
+    }
+}
+
+/// Historique d'un bloc.
+#[derive(Clone, Copy)]
+struct Record {
+    addr: usize,
+    size: usize,
+    block_size: usize,
+    allocated_by: [usize; DEPTH],
+    freed_by: Option<[usize; DEPTH]>,
+}
+
+impl Record {
+    const EMPTY: Record = Record { addr: 0, size: 0, block_size: 0, allocated_by: [0; DEPTH], freed_by: None };
+}
+
+/// Mémoire fantôme d'un heap et historique des derniers blocs.
+pub struct Shadow {
+    /// Début de la zone surveillée (aligné sur `GRANULE`).
+    start: usize,
+    /// Fin de la zone surveillée ; vide tant que `init` n'a pas été appelé.
+    end: usize,
+    /// Un octet par granule de `start..end`.
+    shadow: *mut u8,
+    records: [Record; KASAN_RECORDS],
+    /// Nombre total de blocs consignés.
+    count: usize,
+}
+
+impl Shadow {
+    /// Crée une mémoire fantôme vide : aucun accès n'est contrôlé.
+    pub const fn new() -> Self {
+        Shadow {
+            start: 0,
+            end: 0,
+            shadow: core::ptr::null_mut(),
+            records: [Record::EMPTY; KASAN_RECORDS],
+            count: 0,
+        }
+    }
+
+    /// Réserve la mémoire fantôme à la fin de `heap_start..heap_start + heap_size`
+    /// et marque le reste comme non distribué. Retourne la taille laissée au heap.
+    ///
+    /// # Safety
+    /// La région doit être valide et réservée au heap.
+    pub unsafe fn init(&mut self, heap_start: usize, heap_size: usize) -> usize {
+        let shadow_size = heap_size.div_ceil(GRANULE + 1) + 2;
+        let heap_size = heap_size.saturating_sub(shadow_size);
+        self.start = heap_start & !(GRANULE - 1);
+        self.end = heap_start + heap_size;
+        self.shadow = self.end as *mut u8;
+        self.shadow.write_bytes(UNALLOCATED, (self.end - self.start).div_ceil(GRANULE));
+        heap_size
+    }
+
+    /// Marque accessibles les `size` premiers octets du bloc `addr` de
+    /// `block_size` octets, le reste en zone rouge, et consigne l'allocation.
+    ///
+    /// Ne doit pas être inliné : la remontée de pile part de son propre frame.
+    #[inline(never)]
+    pub fn on_alloc(&mut self, addr: usize, size: usize, block_size: usize) {
+        if !self.covers(addr) {
+            return;
+        }
+        self.unpoison(addr, size, block_size);
+        self.records[self.count % KASAN_RECORDS] =
+            Record { addr, size, block_size, allocated_by: frames::callers(), freed_by: None };
+        self.count += 1;
+    }
+
+    /// Marque libéré le bloc `addr` de `block_size` octets.
+    ///
+    /// Ne doit pas être inliné : la remontée de pile part de son propre frame.
+    #[inline(never)]
+    pub fn on_dealloc(&mut self, addr: usize, block_size: usize) {
+        if !self.covers(addr) {
+            return;
+        }
+        self.fill(addr, addr + block_size, FREED);
+        let callers = frames::callers();
+        if let Some(record) = self.record_mut(addr) {
+            record.freed_by = Some(callers);
+        }
+    }
+
+    /// Met à jour un bloc redimensionné sur place : `new_size` octets
+    /// accessibles, zone rouge jusqu'à `new_block`, et l'ancienne fin de bloc
+    /// (au-delà de `new_block`) rendue au heap.
+    pub fn on_resize(&mut self, addr: usize, new_size: usize, old_block: usize, new_block: usize) {
+        if !self.covers(addr) {
+            return;
+        }
+        self.unpoison(addr, new_size, new_block);
+        if old_block > new_block {
+            self.fill(addr + new_block, addr + old_block, FREED);
+        }
+        if let Some(record) = self.record_mut(addr) {
+            record.size = new_size;
+            record.block_size = new_block;
+        }
+    }
+
+    /// Vérifie un accès de `size` octets à partir de `addr`. Les octets hors
+    /// du heap surveillé ne sont pas contrôlés.
+    pub fn check(&self, addr: usize, size: usize, access: Access) -> Result<(), Violation> {
+        let end = addr.saturating_add(size).min(self.end);
+        let mut at = addr.max(self.start);
+        while at < end {
+            // SAFETY: `at` est dans `start..end`, couvert par la mémoire fantôme.
+            let shadow = unsafe { *self.granule(at) };
+            if shadow == 0 {
+                at = (at & !(GRANULE - 1)) + GRANULE;
+                continue;
+            }
+            if (shadow as usize) < GRANULE && at % GRANULE < shadow as usize {
+                at += 1;
+                continue;
+            }
+            let kind = match shadow {
+                FREED => ViolationKind::UseAfterFree,
+                UNALLOCATED => ViolationKind::Unallocated,
+                _ => ViolationKind::OutOfBounds,
+            };
+            return Err(Violation { kind, access, addr, size, bad_addr: at });
+        }
+        Ok(())
+    }
+
+    /// Écrit le rapport de `violation` : historique du bloc concerné, s'il est
+    /// encore connu, et octets fantômes autour du premier octet fautif.
+    pub fn report(&self, violation: &Violation, out: &mut dyn fmt::Write) -> fmt::Result {
+        writeln!(out, "kasan: {}", violation)?;
+        let bad = violation.bad_addr;
+        let record = self.recent().find(|r| r.addr <= bad && bad < r.addr + r.block_size);
+        match record {
+            Some(record) => {
+                writeln!(
+                    out,
+                    "kasan: block {:#x}: {} bytes requested, {} reserved",
+                    record.addr, record.size, record.block_size
+                )?;
+                write_callers(out, "allocated by", &record.allocated_by)?;
+                if let Some(freed_by) = &record.freed_by {
+                    write_callers(out, "freed by", freed_by)?;
+                }
+            }
+            None => writeln!(out, "kasan: no record of a block at this address")?,
+        }
+        let bad_granule = bad & !(GRANULE - 1);
+        let first = bad_granule.saturating_sub(4 * GRANULE).max(self.start);
+        let last = (bad_granule + 5 * GRANULE).min(align_up(self.end, GRANULE));
+        write!(out, "kasan: shadow {:#x}:", first)?;
+        for granule in (first..last).step_by(GRANULE) {
+            // SAFETY: `granule` est dans la zone surveillée.
+            let shadow = unsafe { *self.granule(granule) };
+            if granule == bad_granule {
+                write!(out, " [{:02x}]", shadow)?;
+            } else {
+                write!(out, " {:02x}", shadow)?;
+            }
+        }
+        out.write_char('\n')
+    }
+
+    /// Indique si `addr` est dans le heap surveillé.
+    fn covers(&self, addr: usize) -> bool {
+        self.start <= addr && addr < self.end
+    }
+
+    /// Octet fantôme du granule contenant `addr`.
+    fn granule(&self, addr: usize) -> *mut u8 {
+        self.shadow.wrapping_add((addr - self.start) / GRANULE)
+    }
+
+    /// Rend accessibles `size` octets à partir de `addr` (aligné sur
+    /// `GRANULE`) et place la zone rouge jusqu'à `addr + block_size`.
+    fn unpoison(&mut self, addr: usize, size: usize, block_size: usize) {
+        let whole = size & !(GRANULE - 1);
+        self.fill(addr, addr + whole, 0);
+        if whole < size {
+            // SAFETY: le bloc est dans la zone surveillée.
+            unsafe { *self.granule(addr + whole) = (size - whole) as u8 };
+        }
+        self.fill(align_up(addr + size, GRANULE), addr + block_size, REDZONE);
+    }
+
+    /// Donne la valeur `value` aux granules de `from..to` situés dans la zone
+    /// surveillée.
+    fn fill(&mut self, from: usize, to: usize, value: u8) {
+        let from = from.max(self.start);
+        let to = to.min(self.end);
+        if from < to {
+            let count = (to - from).div_ceil(GRANULE);
+            // SAFETY: les granules de `from..to` sont couverts par la mémoire fantôme.
+            unsafe { self.granule(from).write_bytes(value, count) };
+        }
+    }
+
+    /// Blocs consignés, du plus récent au plus ancien.
+    fn recent(&self) -> impl Iterator<Item = &Record> {
+        let kept = self.count.min(KASAN_RECORDS);
+        (1..=kept).map(move |back| &self.records[(self.count - back) % KASAN_RECORDS])
+    }
+
+    /// Historique le plus récent du bloc vivant `addr`.
+    fn record_mut(&mut self, addr: usize) -> Option<&mut Record> {
+        let kept = self.count.min(KASAN_RECORDS);
+        let slot = (1..=kept)
+            .map(|back| (self.count - back) % KASAN_RECORDS)
+            .find(|&slot| self.records[slot].addr == addr && self.records[slot].freed_by.is_none())?;
+        Some(&mut self.records[slot])
+    }
+}
+
+/// Écrit une ligne `kasan: <what> 0x…,0x…`.
+fn write_callers(out: &mut dyn fmt::Write, what: &str, callers: &[usize; DEPTH]) -> fmt::Result {
+    write!(out, "kasan: {} ", what)?;
+    for (i, caller) in callers.iter().take_while(|&&caller| caller != 0).enumerate() {
+        if i > 0 {
+            out.write_char(',')?;
+        }
+        write!(out, "{:#x}", caller)?;
+    }
+    out.write_char('\n')
+}
@@ -0,0 +1,324 @@
+//! Allocateur de second niveau pour les gros objets et les tailles variables.
+//!
+//! Les régions libres forment une liste simplement chaînée triée par adresse.
+//! L'allocation prend la première région suffisante (first-fit) et la
+//! libération réinsère la région en la fusionnant avec ses voisines, ce qui
+//! limite la fragmentation externe.
+//!
+//! Lorsque la zone est fournie remplie de zéros, l'allocateur retient la
+//! limite au-delà de laquelle rien n'a encore été distribué : les blocs pris
+//! au-delà sont nuls, hormis les en-têtes de régions libres qu'il efface.
+
+use core::alloc::Layout;
+use core::mem::size_of;
+use core::ptr::null_mut;
+
+use super::{align_up, Page, PAGE_SIZE};
+
+/// En-tête d'une région libre, stocké au début de la région elle-même.
+struct ListNode {
+    /// Taille de la région en octets (en-tête compris).
+    size: usize,
+    /// Région libre suivante (adresse strictement supérieure).
+    next: *mut ListNode,
+}
+
+/// Granularité des régions : toute région a une taille et une adresse
+/// multiples de `GRANULE`, ce qui garantit qu'un reste non nul peut toujours
+/// accueillir un `ListNode`.
+const GRANULE: usize = size_of::<ListNode>();
+
+/// Statistiques de l'allocateur de gros objets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LargeStats {
+    /// Taille de la zone gérée.
+    pub heap_size: usize,
+    /// Octets libres (somme des régions libres).
+    pub free_bytes: usize,
+    /// Taille de la plus grande région libre.
+    pub largest_free: usize,
+    /// Nombre de blocs actuellement alloués.
+    pub in_use: usize,
+    /// Maximum atteint par `in_use`.
+    pub peak_in_use: usize,
+    /// Allocations refusées faute de région suffisante.
+    pub failed: usize,
+    /// Somme des tailles demandées des blocs alloués.
+    pub bytes_requested: usize,
+    /// Octets effectivement réservés (arrondis à `GRANULE`).
+    pub bytes_allocated: usize,
+    /// Pages actuellement prêtées aux slabs.
+    pub slab_pages: usize,
+}
+
+/// Allocateur first-fit à liste de régions libres, avec coalescence.
+pub struct LinkedListAllocator {
+    /// Première région libre (`null` si aucune).
+    head: *mut ListNode,
+    /// Début de la zone gérée (0 tant que `init` n'a pas été appelé).
+    heap_start: usize,
+    /// Fin (exclue) de la zone gérée.
+    heap_end: usize,
+    /// Au-delà de cette adresse, la zone n'a jamais été distribuée et ne
+    /// contient que des zéros, à l'exception des en-têtes de régions libres
+    /// (`heap_end` si le contenu initial de la zone est inconnu).
+    pristine: usize,
+    /// Compteurs d'utilisation (les champs de régions libres sont calculés
+    /// à la demande par `stats`).
+    stats: LargeStats,
+}
+
+impl LinkedListAllocator {
+    /// Crée un allocateur vide, sans région associée.
+    pub const fn new() -> Self {
+        LinkedListAllocator {
+            head: null_mut(),
+            heap_start: 0,
+            heap_end: 0,
+            pristine: 0,
+            stats: LargeStats {
+                heap_size: 0,
+                free_bytes: 0,
+                largest_free: 0,
+                in_use: 0,
+                peak_in_use: 0,
+                failed: 0,
+                bytes_requested: 0,
+                bytes_allocated: 0,
+                slab_pages: 0,
+            },
+        }
+    }
+
+    /// Confie la zone `heap_start..heap_start + heap_size` à l'allocateur.
+    ///
+    /// `zeroed` indique que la zone est entièrement remplie de zéros.
+    ///
+    /// # Safety
+    /// - La zone doit être de la mémoire valide, inutilisée par ailleurs.
+    /// - Doit être appelé une seule fois.
+    pub unsafe fn init(&mut self, heap_start: usize, heap_size: usize, zeroed: bool) {
+        let start = align_up(heap_start, GRANULE);
+        let end = (heap_start + heap_size) & !(GRANULE - 1);
+        if end <= start {
+            return;
+        }
+        self.heap_start = start;
+        self.heap_end = end;
+        self.pristine = if zeroed { start } else { end };
+        self.stats.heap_size = end - start;
+        self.add_free_region(start, end - start);
+    }
+
+    /// Indique si `ptr` appartient à la zone gérée par cet allocateur.
+    pub fn contains(&self, ptr: *mut u8) -> bool {
+        let addr = ptr as usize;
+        addr >= self.heap_start && addr < self.heap_end
+    }
+
+    /// Alloue un bloc respectant la taille et l'alignement de `layout`.
+    ///
+    /// Retourne le bloc (`null_mut()` si aucune région libre ne convient) et
+    /// `true` si son contenu est garanti nul.
+    ///
+    /// # Safety
+    /// - Appel protégé par un `Mutex` pour éviter les accès concurrents.
+    pub unsafe fn alloc(&mut self, layout: Layout) -> (*mut u8, bool) {
+        let (size, align) = Self::size_align(layout);
+        let (ptr, zeroed) = self.alloc_region(size, align);
+        if ptr.is_null() {
+            self.stats.failed += 1;
+            return (ptr, false);
+        }
+        self.stats.in_use += 1;
+        self.stats.peak_in_use = self.stats.peak_in_use.max(self.stats.in_use);
+        self.stats.bytes_requested += layout.size();
+        self.stats.bytes_allocated += size;
+        (ptr, zeroed)
+    }
+
+    /// Prête une page alignée à un slab, ou retourne `None`.
+    pub fn alloc_page(&mut self) -> Option<Page> {
+        // SAFETY: la liste ne contient que des régions libres de la zone gérée.
+        let (addr, zeroed) = unsafe { self.alloc_region(PAGE_SIZE, PAGE_SIZE) };
+        if addr.is_null() {
+            return None;
+        }
+        self.stats.slab_pages += 1;
+        Some(Page { addr, zeroed })
+    }
+
+    /// Reprend une page prêtée par `alloc_page`.
+    ///
+    /// # Safety
+    /// `page` doit provenir de `alloc_page` et ne plus être utilisée.
+    pub unsafe fn dealloc_page(&mut self, page: *mut u8) {
+        self.add_free_region(page as usize, PAGE_SIZE);
+        self.stats.slab_pages -= 1;
+    }
+
+    /// Découpe dans la première région libre suffisante un bloc de `size`
+    /// octets aligné sur `align` (tous deux multiples de `GRANULE`).
+    ///
+    /// Retourne aussi `true` si le bloc est pris entièrement au-delà de
+    /// `pristine` ; l'en-tête de région qu'il recouvre est alors effacé.
+    unsafe fn alloc_region(&mut self, size: usize, align: usize) -> (*mut u8, bool) {
+        let mut link: *mut *mut ListNode = &mut self.head;
+        while !(*link).is_null() {
+            let region = *link;
+            let start = region as usize;
+            let end = start + (*region).size;
+            let alloc_start = align_up(start, align);
+            let alloc_end = match alloc_start.checked_add(size) {
+                Some(alloc_end) if alloc_end <= end => alloc_end,
+                _ => {
+                    link = &mut (*region).next;
+                    continue;
+                }
+            };
+
+            // Retire la région puis réinsère, dans l'ordre, le préfixe
+            // d'alignement et le reste non utilisés.
+            *link = (*region).next;
+            if alloc_start > start {
+                link = Self::insert_after(link, start, alloc_start - start);
+            }
+            if end > alloc_end {
+                Self::insert_after(link, alloc_end, end - alloc_end);
+            }
+
+            let zeroed = alloc_start >= self.pristine;
+            if zeroed && alloc_start == start {
+                (alloc_start as *mut u8).write_bytes(0, GRANULE);
+            }
+            self.pristine = self.pristine.max(alloc_end);
+            return (alloc_start as *mut u8, zeroed);
+        }
+        (null_mut(), false)
+    }
+
+    /// Rend à l'allocateur un bloc obtenu par `alloc` avec le même `layout`.
+    ///
+    /// Les pointeurs extérieurs à la zone gérée sont ignorés.
+    ///
+    /// # Safety
+    /// - `ptr` et `layout` doivent correspondre à un appel antérieur à `alloc`.
+    pub unsafe fn dealloc(&mut self, ptr: *mut u8, layout: Layout) {
+        if !self.contains(ptr) {
+            return;
+        }
+        let (size, _) = Self::size_align(layout);
+        self.add_free_region(ptr as usize, size);
+        self.stats.in_use -= 1;
+        self.stats.bytes_requested -= layout.size();
+        self.stats.bytes_allocated -= size;
+    }
+
+    /// Change sur place la taille d'un bloc obtenu par `alloc` avec `layout`.
+    ///
+    /// Une réduction rend la fin du bloc ; un agrandissement consomme la
+    /// région libre qui suit immédiatement le bloc, si elle est assez grande.
+    /// Retourne `false`, sans rien modifier, si le bloc doit être déplacé.
+    ///
+    /// # Safety
+    /// - `ptr` et `layout` doivent correspondre à un appel antérieur à `alloc`.
+    pub unsafe fn resize(&mut self, ptr: *mut u8, layout: Layout, new_size: usize) -> bool {
+        if !self.contains(ptr) {
+            return false;
+        }
+        let (old, align) = Self::size_align(layout);
+        let (new, _) = Self::size_align(Layout::from_size_align_unchecked(new_size, align));
+        let addr = ptr as usize;
+
+        if new < old {
+            self.add_free_region(addr + new, old - new);
+        } else if new > old {
+            let mut link: *mut *mut ListNode = &mut self.head;
+            while !(*link).is_null() && (*link as usize) < addr + old {
+                link = &mut (**link).next;
+            }
+            let region = *link;
+            if region as usize != addr + old || (*region).size < new - old {
+                return false;
+            }
+            *link = (*region).next;
+            let end = region as usize + (*region).size;
+            if end > addr + new {
+                Self::insert_after(link, addr + new, end - (addr + new));
+            }
+            self.pristine = self.pristine.max(addr + new);
+        }
+
+        self.stats.bytes_requested = self.stats.bytes_requested - layout.size() + new_size;
+        self.stats.bytes_allocated = self.stats.bytes_allocated - old + new;
+        true
+    }
+
+    /// Retourne les compteurs d'utilisation et l'état des régions libres.
+    pub fn stats(&self) -> LargeStats {
+        let mut stats = self.stats;
+        let mut node = self.head;
+        while !node.is_null() {
+            // SAFETY: la liste ne contient que des nœuds écrits par `add_free_region`
+            // ou `insert_after`, dans la zone gérée.
+            let size = unsafe { (*node).size };
+            stats.free_bytes += size;
+            stats.largest_free = stats.largest_free.max(size);
+            node = unsafe { (*node).next };
+        }
+        stats
+    }
+
+    /// Insère la région `addr..addr + size` dans la liste triée en la
+    /// fusionnant avec les régions adjacentes.
+    ///
+    /// # Safety
+    /// La région doit être libre, alignée sur `GRANULE` et de taille multiple
+    /// de `GRANULE`.
+    unsafe fn add_free_region(&mut self, addr: usize, size: usize) {
+        let mut prev: *mut ListNode = null_mut();
+        let mut next = self.head;
+        while !next.is_null() && (next as usize) < addr {
+            prev = next;
+            next = (*next).next;
+        }
+
+        let node = addr as *mut ListNode;
+        node.write(ListNode { size, next });
+        if prev.is_null() {
+            self.head = node;
+        } else {
+            (*prev).next = node;
+        }
+
+        if !next.is_null() && addr + size == next as usize {
+            (*node).size += (*next).size;
+            (*node).next = (*next).next;
+            // L'en-tête absorbé ne doit pas rester dans la partie vierge.
+            if next as usize >= self.pristine {
+                next.write_bytes(0, 1);
+            }
+        }
+        if !prev.is_null() && prev as usize + (*prev).size == addr {
+            (*prev).size += (*node).size;
+            (*prev).next = (*node).next;
+        }
+    }
+
+    /// Écrit un nœud libre en `addr` et le chaîne à la place de `*link`.
+    ///
+    /// Retourne le lien `next` du nouveau nœud, pour une insertion suivante.
+    unsafe fn insert_after(link: *mut *mut ListNode, addr: usize, size: usize) -> *mut *mut ListNode {
+        let node = addr as *mut ListNode;
+        node.write(ListNode { size, next: *link });
+        *link = node;
+        &mut (*node).next
+    }
+
+    /// Taille et alignement effectifs d'une requête : arrondis à `GRANULE`.
+    pub fn size_align(layout: Layout) -> (usize, usize) {
+        let align = layout.align().max(GRANULE);
+        let size = align_up(layout.size().max(GRANULE), GRANULE);
+        (size, align)
+    }
+}
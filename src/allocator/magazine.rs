@@ -0,0 +1,277 @@
+//! Magazines par processeur (feature `magazines`).
+//!
+//! Chaque CPU garde, pour chaque classe de taille, un petit tableau de blocs
+//! libres : le « magazine ». `alloc` et `dealloc` d'un bloc de slab s'y
+//! servent sans prendre de verrou. Les interruptions sont masquées le temps
+//! de toucher au magazine, pour qu'un gestionnaire ne le modifie pas en même
+//! temps ; comme les verrous des slabs sont eux aussi pris interruptions
+//! masquées (`irq::IrqMutex`), un gestionnaire d'interruption peut allouer.
+//! Le slab partagé n'est verrouillé que pour recharger un magazine vide ou
+//! vider un magazine plein, par lots de `MAGAZINE_BATCH` blocs.
+//!
+//! Le CPU courant est identifié par `cpu::current`. Au-delà de `MAX_CPUS`,
+//! les blocs passent directement par le slab, sous son verrou.
+//!
+//! Les blocs en magazine restent comptés comme alloués par leur slab ; les
+//! statistiques de `SimpleAllocator` les retranchent. Avec `slab_debug`, ils
+//! sont en revanche libres pour les vérifications : un bloc est contrôlé et
+//! empoisonné en entrant dans un magazine, son poison est vérifié et sa zone
+//! rouge posée en sortant (voir `Slab::check_in`). Ces contrôles prennent le
+//! verrou du slab. Les octets demandés
+//! sont comptés par CPU, un bloc pouvant être libéré sur un autre CPU que
+//! celui qui l'a alloué.
+
+use core::alloc::Layout;
+use core::cell::UnsafeCell;
+use core::ptr::null_mut;
+use core::sync::atomic::{AtomicIsize, AtomicUsize, Ordering};
+
+use super::cpu::{self, MAX_CPUS};
+use super::irq::{without_interrupts, IrqMutexGuard};
+use super::{Page, Slab};
+
+/// Capacité d'un magazine, en blocs.
+pub const MAGAZINE_SIZE: usize = 32;
+/// Nombre de blocs échangés avec le slab à chaque recharge ou vidage.
+pub const MAGAZINE_BATCH: usize = MAGAZINE_SIZE / 2;
+
+/// Layout transmis au slab : les octets demandés sont comptés ici.
+const UNTRACKED: Layout = Layout::new::<()>();
+
+/// Blocs libres d'une classe de taille, réservés à un CPU.
+struct Magazine {
+    /// Blocs libres ; seuls les `count` premiers sont valides.
+    blocks: UnsafeCell<[*mut u8; MAGAZINE_SIZE]>,
+    /// Nombre de blocs présents. Modifié par le seul CPU propriétaire,
+    /// interruptions masquées ; lu par les statistiques.
+    count: AtomicUsize,
+    /// Octets demandés des blocs alloués sur ce CPU, moins ceux des blocs
+    /// libérés sur ce CPU (peut être négatif).
+    requested: AtomicIsize,
+}
+
+impl Magazine {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY: Magazine = Magazine {
+        blocks: UnsafeCell::new([null_mut(); MAGAZINE_SIZE]),
+        count: AtomicUsize::new(0),
+        requested: AtomicIsize::new(0),
+    };
+
+    /// Retire un bloc du magazine.
+    ///
+    /// # Safety
+    /// Appel sur le CPU propriétaire, interruptions masquées.
+    unsafe fn pop(&self) -> Option<*mut u8> {
+        let count = self.count.load(Ordering::Relaxed);
+        if count == 0 {
+            return None;
+        }
+        self.count.store(count - 1, Ordering::Relaxed);
+        Some((*self.blocks.get())[count - 1])
+    }
+
+    /// Range `block` dans le magazine, qui ne doit pas être plein.
+    ///
+    /// # Safety
+    /// Appel sur le CPU propriétaire, interruptions masquées.
+    unsafe fn push(&self, block: *mut u8) {
+        let count = self.count.load(Ordering::Relaxed);
+        (*self.blocks.get())[count] = block;
+        self.count.store(count + 1, Ordering::Relaxed);
+    }
+
+    /// Prend au plus `MAGAZINE_BATCH` blocs à `slab`. Une page neuve n'est
+    /// demandée à `grow` que si le slab n'a plus aucun bloc libre.
+    ///
+    /// # Safety
+    /// Comme `pop` ; `slab` est verrouillé et `grow` respecte le contrat de
+    /// `Slab::alloc`.
+    unsafe fn refill(&self, slab: &mut Slab, grow: impl Fn() -> Option<Page>) {
+        for i in 0..MAGAZINE_BATCH {
+            if i > 0 && slab.partial.is_null() {
+                break;
+            }
+            let (block, _) = slab.alloc(UNTRACKED, &grow);
+            if block.is_null() {
+                break;
+            }
+            #[cfg(feature = "slab_debug")]
+            slab.check_in(block, &UNTRACKED);
+            self.push(block);
+        }
+    }
+
+    /// Rend au plus `count` blocs à `slab`.
+    ///
+    /// # Safety
+    /// Comme `pop` ; `slab` est verrouillé et `release` respecte le contrat
+    /// de `Slab::dealloc`.
+    unsafe fn flush(&self, count: usize, slab: &mut Slab, release: impl Fn(*mut u8)) -> usize {
+        let mut flushed = 0;
+        while flushed < count {
+            match self.pop() {
+                Some(block) => {
+                    #[cfg(feature = "slab_debug")]
+                    slab.check_out(block, &UNTRACKED);
+                    slab.dealloc(block, UNTRACKED, &release)
+                }
+                None => break,
+            }
+            flushed += 1;
+        }
+        flushed
+    }
+}
+
+/// Magazines de tous les CPU pour les `CLASSES` classes de taille.
+pub struct Magazines<const CLASSES: usize> {
+    cpus: [[Magazine; CLASSES]; MAX_CPUS],
+    /// Octets demandés des blocs servis hors magazine (CPU au-delà de `MAX_CPUS`).
+    spill: [AtomicIsize; CLASSES],
+}
+
+// SAFETY: un magazine n'est modifié que par son CPU, interruptions masquées.
+unsafe impl<const CLASSES: usize> Sync for Magazines<CLASSES> {}
+
+impl<const CLASSES: usize> Magazines<CLASSES> {
+    /// Crée des magazines vides.
+    pub const fn new() -> Self {
+        Magazines {
+            cpus: [const { [Magazine::EMPTY; CLASSES] }; MAX_CPUS],
+            spill: [const { AtomicIsize::new(0) }; CLASSES],
+        }
+    }
+
+    /// Alloue un bloc de la classe `class` pour `layout`.
+    ///
+    /// Le magazine du CPU courant est rechargé depuis `slab` s'il est vide.
+    /// Le bloc n'est jamais garanti nul.
+    ///
+    /// # Safety
+    /// `grow` respecte le contrat de `Slab::alloc`.
+    pub unsafe fn alloc<'a>(
+        &self,
+        class: usize,
+        layout: Layout,
+        slab: impl Fn() -> IrqMutexGuard<'a, Slab>,
+        grow: impl Fn() -> Option<Page>,
+    ) -> (*mut u8, bool) {
+        without_interrupts(|| {
+            let local = self.local(class);
+            let block = match local {
+                Some(magazine) => match magazine.pop() {
+                    Some(block) => block,
+                    None => {
+                        magazine.refill(&mut slab(), grow);
+                        magazine.pop().unwrap_or(null_mut())
+                    }
+                },
+                None => {
+                    let mut slab = slab();
+                    let (block, _) = slab.alloc(UNTRACKED, grow);
+                    // Hors magazine, le bloc passe aussi par l'état libre.
+                    #[cfg(feature = "slab_debug")]
+                    if !block.is_null() {
+                        slab.check_in(block, &UNTRACKED);
+                    }
+                    block
+                }
+            };
+            if !block.is_null() {
+                #[cfg(feature = "slab_debug")]
+                slab().check_out(block, &layout);
+                self.requested(local, class).fetch_add(layout.size() as isize, Ordering::Relaxed);
+            }
+            (block, false)
+        })
+    }
+
+    /// Libère un bloc de la classe `class` alloué avec `layout`.
+    ///
+    /// Si le magazine du CPU courant est plein, `MAGAZINE_BATCH` blocs sont
+    /// d'abord rendus à `slab`.
+    ///
+    /// # Safety
+    /// `ptr` provient de `alloc` pour cette classe ; `release` respecte le
+    /// contrat de `Slab::dealloc`.
+    pub unsafe fn dealloc<'a>(
+        &self,
+        class: usize,
+        ptr: *mut u8,
+        layout: Layout,
+        slab: impl Fn() -> IrqMutexGuard<'a, Slab>,
+        release: impl Fn(*mut u8),
+    ) {
+        without_interrupts(|| {
+            let local = self.local(class);
+            #[cfg(feature = "slab_debug")]
+            slab().check_in(ptr, &layout);
+            self.requested(local, class).fetch_sub(layout.size() as isize, Ordering::Relaxed);
+            match local {
+                Some(magazine) => {
+                    if magazine.count.load(Ordering::Relaxed) == MAGAZINE_SIZE {
+                        magazine.flush(MAGAZINE_BATCH, &mut slab(), release);
+                    }
+                    magazine.push(ptr);
+                }
+                None => {
+                    let mut slab = slab();
+                    #[cfg(feature = "slab_debug")]
+                    slab.check_out(ptr, &UNTRACKED);
+                    slab.dealloc(ptr, UNTRACKED, release)
+                }
+            }
+        })
+    }
+
+    /// Rend à `slab` tous les blocs du magazine `class` du CPU courant.
+    /// Retourne le nombre de blocs rendus.
+    ///
+    /// # Safety
+    /// `release` respecte le contrat de `Slab::dealloc`.
+    pub unsafe fn drain<'a>(
+        &self,
+        class: usize,
+        slab: impl FnOnce() -> IrqMutexGuard<'a, Slab>,
+        release: impl Fn(*mut u8),
+    ) -> usize {
+        without_interrupts(|| match self.local(class) {
+            Some(magazine) if magazine.count.load(Ordering::Relaxed) > 0 => {
+                magazine.flush(MAGAZINE_SIZE, &mut slab(), release)
+            }
+            _ => 0,
+        })
+    }
+
+    /// Ajoute `delta` aux octets demandés de la classe `class` (allocation,
+    /// libération ou `realloc` sur place).
+    pub fn account(&self, class: usize, delta: isize) {
+        self.requested(self.local(class), class).fetch_add(delta, Ordering::Relaxed);
+    }
+
+    /// Blocs en magazine et octets demandés de la classe `class`, tous CPU
+    /// confondus.
+    pub fn usage(&self, class: usize) -> (usize, usize) {
+        let mut cached = 0;
+        let mut requested = self.spill[class].load(Ordering::Relaxed);
+        for cpu in self.cpus.iter() {
+            cached += cpu[class].count.load(Ordering::Relaxed);
+            requested += cpu[class].requested.load(Ordering::Relaxed);
+        }
+        (cached, requested.max(0) as usize)
+    }
+
+    /// Compteur d'octets demandés de `local`, ou de la classe `class` hors magazine.
+    fn requested<'a>(&'a self, local: Option<&'a Magazine>, class: usize) -> &'a AtomicIsize {
+        match local {
+            Some(magazine) => &magazine.requested,
+            None => &self.spill[class],
+        }
+    }
+
+    /// Magazine de la classe `class` du CPU courant, s'il en a un.
+    fn local(&self, class: usize) -> Option<&Magazine> {
+        self.cpus.get(cpu::current()).map(|cpu| &cpu[class])
+    }
+}
@@ -0,0 +1,151 @@
+//! Récupération de mémoire avant l'OOM : registre de « shrinkers ».
+//!
+//! Un sous-système qui garde de la mémoire libérable (cache de secteurs FAT32,
+//! tampons de journal…) enregistre un `Shrinker`. Quand une allocation échoue,
+//! l'allocateur appelle chaque shrinker puis réessaie ; il n'abandonne (et ne
+//! laisse l'`alloc_error_handler` intervenir) que lorsqu'aucun n'a plus rien
+//! rendu. Les compteurs par shrinker permettent d'observer ce qui a tourné.
+//!
+//! Une seule passe tourne à la fois : un CPU dont l'allocation échoue pendant
+//! la passe d'un autre attend qu'elle se termine, puis réessaie si elle a
+//! rendu de la mémoire. Sur le CPU qui la mène, une allocation faite par un
+//! shrinker échoue directement. Comme tout verrou de l'allocateur, celui de
+//! la passe est tenu interruptions masquées : les shrinkers tournent ainsi.
+
+use core::alloc::Layout;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use super::cpu::{self, MAX_CPUS};
+use super::irq::IrqMutex;
+
+/// Nombre maximal de shrinkers enregistrés.
+pub const MAX_SHRINKERS: usize = 8;
+
+/// Callback de récupération de mémoire.
+#[derive(Debug)]
+pub struct Shrinker {
+    /// Nom affiché dans les statistiques.
+    pub name: &'static str,
+    /// Libère ce qui peut l'être pour satisfaire `layout` et retourne le
+    /// nombre d'octets rendus (0 si rien n'est récupérable). Ne doit pas
+    /// allouer : une allocation pendant la récupération échoue directement,
+    /// ou attend indéfiniment la passe sur un CPU au-delà de `MAX_CPUS`.
+    pub shrink: fn(&Layout) -> usize,
+}
+
+/// Compteurs d'un shrinker enregistré.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShrinkerStats {
+    /// Nom du shrinker.
+    pub name: &'static str,
+    /// Nombre d'appels.
+    pub runs: usize,
+    /// Total des octets rendus.
+    pub reclaimed: usize,
+}
+
+/// Erreur de `register_shrinker` : les `MAX_SHRINKERS` places sont prises.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegistryFull;
+
+#[derive(Clone, Copy)]
+struct Slot {
+    shrinker: Option<&'static Shrinker>,
+    runs: usize,
+    reclaimed: usize,
+}
+
+impl Slot {
+    const EMPTY: Slot = Slot { shrinker: None, runs: 0, reclaimed: 0 };
+}
+
+/// Registre des shrinkers d'un allocateur.
+pub struct Registry {
+    slots: IrqMutex<[Slot; MAX_SHRINKERS]>,
+    /// Par CPU, vrai pendant que ce CPU récupère de la mémoire (ou attend une
+    /// passe), pour ne pas la relancer depuis une allocation faite par un
+    /// shrinker. Les CPU au-delà de `MAX_CPUS` n'en ont pas : ils attendent
+    /// toujours la passe en cours par le verrou `pass`.
+    reclaiming: [AtomicBool; MAX_CPUS],
+    /// Passe en cours ; vrai si la dernière passe terminée a rendu des octets.
+    pass: IrqMutex<bool>,
+    /// Nombre de passes terminées.
+    passes: AtomicUsize,
+}
+
+impl Registry {
+    /// Crée un registre vide.
+    pub const fn new() -> Self {
+        Registry {
+            slots: IrqMutex::new([Slot::EMPTY; MAX_SHRINKERS]),
+            reclaiming: [const { AtomicBool::new(false) }; MAX_CPUS],
+            pass: IrqMutex::new(false),
+            passes: AtomicUsize::new(0),
+        }
+    }
+
+    /// Ajoute `shrinker` à la fin du registre.
+    pub fn register(&self, shrinker: &'static Shrinker) -> Result<(), RegistryFull> {
+        let mut slots = self.slots.lock();
+        let slot = slots.iter_mut().find(|slot| slot.shrinker.is_none()).ok_or(RegistryFull)?;
+        *slot = Slot { shrinker: Some(shrinker), ..Slot::EMPTY };
+        Ok(())
+    }
+
+    /// Appelle chaque shrinker, dans l'ordre d'enregistrement, pour une
+    /// allocation `layout` qui vient d'échouer.
+    ///
+    /// Retourne `true` si au moins un octet a été rendu (l'allocation mérite
+    /// d'être retentée). Les shrinkers sont appelés sans verrou de l'allocateur.
+    /// Si un autre CPU mène déjà une passe, l'attend et retourne son résultat
+    /// au lieu d'en relancer une.
+    pub fn reclaim(&self, layout: &Layout) -> bool {
+        let reclaiming = self.reclaiming.get(cpu::current());
+        if reclaiming.is_some_and(|reclaiming| reclaiming.swap(true, Ordering::Acquire)) {
+            return false;
+        }
+        let seen = self.passes.load(Ordering::Acquire);
+        let mut pass = self.pass.lock();
+        let reclaimed = if self.passes.load(Ordering::Acquire) != seen {
+            // Une passe s'est terminée pendant l'attente : l'allocation est
+            // retentée si elle a rendu de la mémoire.
+            *pass
+        } else {
+            *pass = self.run(layout) > 0;
+            self.passes.fetch_add(1, Ordering::Release);
+            *pass
+        };
+        drop(pass);
+        if let Some(reclaiming) = reclaiming {
+            reclaiming.store(false, Ordering::Release);
+        }
+        reclaimed
+    }
+
+    /// Appelle chaque shrinker et retourne le total des octets rendus.
+    fn run(&self, layout: &Layout) -> usize {
+        let shrinkers = self.slots.lock().map(|slot| slot.shrinker);
+        let mut total = 0;
+        for (i, shrinker) in shrinkers.iter().enumerate() {
+            if let Some(shrinker) = shrinker {
+                let reclaimed = (shrinker.shrink)(layout);
+                let mut slots = self.slots.lock();
+                slots[i].runs += 1;
+                slots[i].reclaimed += reclaimed;
+                total += reclaimed;
+            }
+        }
+        total
+    }
+
+    /// Compteurs des shrinkers enregistrés, dans l'ordre d'enregistrement.
+    pub fn stats(&self) -> [Option<ShrinkerStats>; MAX_SHRINKERS] {
+        self.slots.lock().map(|slot| {
+            slot.shrinker.map(|shrinker| ShrinkerStats {
+                name: shrinker.name,
+                runs: slot.runs,
+                reclaimed: slot.reclaimed,
+            })
+        })
+    }
+}
@@ -0,0 +1,158 @@
+//! Traçage des allocations (feature `alloc_trace`).
+//!
+//! Chaque allocation et libération est consignée dans un tampon circulaire de
+//! taille fixe, sans allocation : les `TRACE_LEN` derniers événements restent
+//! disponibles pour analyser un OOM ou une fuite. `write` les restitue, une
+//! ligne par événement, dans un format `clé=valeur` facile à relire sur l'hôte :
+//!
+//! ```text
+//! trace-begin events=1042 dropped=786
+//! trace seq=786 op=alloc ts=1234567 addr=0x444400001010 size=24 align=8 class=32 caller=0x2011a3,0x20455e,0x2088f0,0x201016
+//! trace-end
+//! ```
+//!
+//! `class` vaut la taille de bloc du slab ou `large` ; `addr=0x0` signale une
+//! allocation refusée. `caller` donne les premières adresses de retour de la
+//! pile, à partir de l'appelant de l'allocateur (la chaîne des frame pointers
+//! est suivie, voir le module `frames`).
+
+use core::alloc::Layout;
+use core::fmt;
+
+use super::frames;
+
+/// Nombre d'événements conservés.
+pub const TRACE_LEN: usize = 256;
+/// Nombre d'adresses de retour relevées par événement.
+pub const TRACE_DEPTH: usize = 4;
+
+/// Nature d'un événement tracé.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Alloc,
+    Dealloc,
+}
+
+/// Un événement du tampon.
+#[derive(Debug, Clone, Copy)]
+struct Record {
+    op: Op,
+    timestamp: u64,
+    addr: usize,
+    size: usize,
+    align: usize,
+    class: Option<usize>,
+    callers: [usize; TRACE_DEPTH],
+}
+
+impl Record {
+    const EMPTY: Record = Record {
+        op: Op::Alloc,
+        timestamp: 0,
+        addr: 0,
+        size: 0,
+        align: 0,
+        class: None,
+        callers: [0; TRACE_DEPTH],
+    };
+}
+
+/// Tampon circulaire des derniers événements.
+pub struct Ring {
+    records: [Record; TRACE_LEN],
+    /// Nombre total d'événements enregistrés depuis le démarrage.
+    count: usize,
+}
+
+impl Ring {
+    /// Crée un tampon vide.
+    pub const fn new() -> Self {
+        Ring { records: [Record::EMPTY; TRACE_LEN], count: 0 }
+    }
+
+    /// Consigne un événement portant sur le bloc `ptr` décrit par `layout`,
+    /// servi par le slab de blocs de `class` octets (`None` : gros objet).
+    ///
+    /// Ne doit pas être inliné : la remontée de pile part de son propre frame.
+    #[inline(never)]
+    pub fn record(&mut self, op: Op, ptr: *mut u8, layout: &Layout, class: Option<usize>) {
+        let record = Record {
+            op,
+            // SAFETY: `rdtsc` est disponible sur tout processeur x86_64.
+            timestamp: unsafe { core::arch::x86_64::_rdtsc() },
+            addr: ptr as usize,
+            size: layout.size(),
+            align: layout.align(),
+            class,
+            callers: frames::callers(),
+        };
+        self.records[self.count % TRACE_LEN] = record;
+        self.count += 1;
+    }
+
+    /// Nombre total d'événements enregistrés.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Écrit les événements conservés, du plus ancien au plus récent.
+    pub fn write(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        let first = self.first();
+        writeln!(out, "trace-begin events={} dropped={}", self.count, first)?;
+        for seq in first..self.count {
+            self.write_record(seq, out)?;
+        }
+        writeln!(out, "trace-end")
+    }
+
+    /// Écrit les allocations réussies depuis l'événement `since` qui n'ont pas
+    /// été suivies d'une libération du même bloc, dans le format de `write`.
+    ///
+    /// Les événements déjà écrasés dans le tampon sont signalés par `dropped`.
+    pub fn write_live_since(&self, since: usize, out: &mut dyn fmt::Write) -> fmt::Result {
+        let first = since.max(self.first());
+        writeln!(out, "trace-begin events={} dropped={}", self.count - since, first - since)?;
+        for seq in first..self.count {
+            let record = &self.records[seq % TRACE_LEN];
+            let freed = (seq + 1..self.count).any(|later| {
+                let later = &self.records[later % TRACE_LEN];
+                later.op == Op::Dealloc && later.addr == record.addr
+            });
+            if record.op == Op::Alloc && record.addr != 0 && !freed {
+                self.write_record(seq, out)?;
+            }
+        }
+        writeln!(out, "trace-end")
+    }
+
+    /// Numéro du plus ancien événement encore présent.
+    fn first(&self) -> usize {
+        self.count.saturating_sub(TRACE_LEN)
+    }
+
+    /// Écrit la ligne de l'événement `seq`.
+    fn write_record(&self, seq: usize, out: &mut dyn fmt::Write) -> fmt::Result {
+        let record = &self.records[seq % TRACE_LEN];
+        let op = match record.op {
+            Op::Alloc => "alloc",
+            Op::Dealloc => "dealloc",
+        };
+        write!(
+            out,
+            "trace seq={} op={} ts={} addr={:#x} size={} align={} class=",
+            seq, op, record.timestamp, record.addr, record.size, record.align
+        )?;
+        match record.class {
+            Some(size) => write!(out, "{}", size)?,
+            None => out.write_str("large")?,
+        }
+        out.write_str(" caller=")?;
+        for (i, caller) in record.callers.iter().take_while(|&&caller| caller != 0).enumerate() {
+            if i > 0 {
+                out.write_char(',')?;
+            }
+            write!(out, "{:#x}", caller)?;
+        }
+        out.write_char('\n')
+    }
+}
@@ -8,6 +8,32 @@ extern crate alloc;
 use alloc::{string::String, vec::Vec};
 use alloc::{format, vec};
 
+/// Valeur FAT d'un cluster libre.
+const FREE_CLUSTER: u32 = 0;
+/// Valeur FAT écrite en fin de chaîne (toute valeur >= `0x0FFF_FFF8` l'est).
+const END_OF_CHAIN: u32 = 0x0FFF_FFFF;
+/// Premier octet d'une entrée de répertoire supprimée.
+const DELETED_ENTRY: u8 = 0xE5;
+/// Taille d'une entrée de répertoire.
+const ENTRY_SIZE: usize = 32;
+/// Attribut des fichiers créés (archive).
+const ATTR_ARCHIVE: u8 = 0x20;
+/// Attribut des répertoires.
+const ATTR_DIRECTORY: u8 = 0x10;
+/// Attribut des entrées de nom long (VFAT).
+const ATTR_LONG_NAME: u8 = 0x0F;
+/// Bits significatifs de l'attribut ; les bits 6 et 7 sont réservés.
+const ATTR_MASK: u8 = 0x3F;
+/// Attribut du nom de volume, également présent dans `ATTR_LONG_NAME`.
+const ATTR_VOLUME_ID: u8 = 0x08;
+/// Position des 13 caractères UCS-2 d'une entrée de nom long.
+const LFN_OFFSETS: [usize; 13] = [1, 3, 5, 7, 9, 14, 16, 18, 20, 22, 24, 28, 30];
+/// Bit du numéro de séquence marquant la dernière entrée d'un nom long,
+/// qui est la première sur le disque.
+const LFN_LAST: u8 = 0x40;
+/// Nombre maximal d'entrées d'un nom long (255 caractères).
+const LFN_MAX_ENTRIES: u8 = 20;
+
 #[derive(Debug, Clone, Copy)]
 pub struct BootSector {
     pub bytes_per_sector: u16,
@@ -16,6 +42,8 @@ pub struct BootSector {
     pub fats: u8,
     pub sectors_per_fat: u32,
     pub root_cluster: u32,
+    /// Nombre total de secteurs du volume (0 si le BPB ne le renseigne pas).
+    pub total_sectors: u32,
 }
 
 #[derive(Debug, Clone)]
@@ -24,6 +52,9 @@ pub struct DirectoryEntry {
     pub attr: u8,
     pub first_cluster: u32,
     pub size: u32,
+    /// Nom long VFAT, si l'entrée est précédée d'une séquence de noms longs
+    /// complète et dont la somme de contrôle correspond au nom court.
+    pub long_name: Option<String>,
 }
 
 #[derive(Debug)]
@@ -31,9 +62,41 @@ pub enum FatError {
     InvalidCluster,
     Io,
     EndOfChain,
+    /// Nom qui ne forme pas un nom 8.3 valide.
+    InvalidName,
+    /// Une entrée du répertoire porte déjà ce nom.
+    AlreadyExists,
+    /// Aucune entrée de ce nom dans le répertoire.
+    NotFound,
+    /// Plus assez de clusters libres.
+    DiskFull,
+    /// Le répertoire à supprimer contient encore des entrées.
+    DirectoryNotEmpty,
+    /// Un composant intermédiaire du chemin, ou le cluster passé comme
+    /// répertoire, n'est pas un répertoire.
+    NotADirectory,
+    /// Le chemin désigne un répertoire là où un fichier est attendu.
+    IsADirectory,
+    /// Secteur de démarrage incohérent : cluster ou FAT de taille nulle.
+    InvalidBootSector,
 }
 
 impl DirectoryEntry {
+    /// Décode une entrée de répertoire de 32 octets.
+    fn parse(raw: &[u8]) -> Self {
+        let mut name = [0u8; 11];
+        name.copy_from_slice(&raw[0..11]);
+        let first_cluster = ((u16::from_le_bytes([raw[20], raw[21]]) as u32) << 16)
+            | u16::from_le_bytes([raw[26], raw[27]]) as u32;
+        let size = u32::from_le_bytes([raw[28], raw[29], raw[30], raw[31]]);
+        Self { name, attr: raw[11], first_cluster, size, long_name: None }
+    }
+
+    /// Indique si l'entrée désigne un répertoire.
+    pub fn is_directory(&self) -> bool {
+        self.attr & ATTR_DIRECTORY != 0
+    }
+
     pub fn filename(&self) -> String {
         let name = core::str::from_utf8(&self.name[..8]).unwrap().trim_end();
         let ext = core::str::from_utf8(&self.name[8..]).unwrap().trim_end();
@@ -54,6 +117,10 @@ impl BootSector {
             fats: buf[16],
             sectors_per_fat: u32::from_le_bytes([buf[36], buf[37], buf[38], buf[39]]),
             root_cluster: u32::from_le_bytes([buf[44], buf[45], buf[46], buf[47]]),
+            total_sectors: match u16::from_le_bytes([buf[19], buf[20]]) {
+                0 => u32::from_le_bytes([buf[32], buf[33], buf[34], buf[35]]),
+                small => small as u32,
+            },
         }
     }
 }
@@ -68,6 +135,10 @@ impl<D: BlockDevice> Fat32<D> {
         let mut buf = [0u8; 512];
         device.read_sector(0, &mut buf);
         let boot_sector = BootSector::parse(&buf);
+        // `cluster_to_lba` et `cluster_count` divisent ou soustraient avec ces champs.
+        if boot_sector.sectors_per_cluster == 0 || boot_sector.sectors_per_fat == 0 {
+            return Err(FatError::InvalidBootSector);
+        }
         Ok(Self { device, boot_sector })
     }
 
@@ -75,6 +146,11 @@ impl<D: BlockDevice> Fat32<D> {
         &self.boot_sector
     }
 
+    /// Rend le périphérique, par exemple pour remonter le volume.
+    pub fn into_device(self) -> D {
+        self.device
+    }
+
     pub fn cluster_size(&self) -> usize {
         self.boot_sector.bytes_per_sector as usize
             * self.boot_sector.sectors_per_cluster as usize
@@ -105,67 +181,638 @@ impl<D: BlockDevice> Fat32<D> {
         entry & 0x0FFF_FFFF
     }
 
+    /// Nombre de clusters de données, bornés par la taille du volume et par
+    /// celle d'une FAT.
+    pub fn cluster_count(&self) -> u32 {
+        let fat_entries = self.boot_sector.sectors_per_fat * 512 / 4 - 2;
+        let total = self.boot_sector.total_sectors;
+        if total == 0 {
+            return fat_entries;
+        }
+        let data_sectors = total.saturating_sub(self.first_data_sector());
+        fat_entries.min(data_sectors / self.boot_sector.sectors_per_cluster as u32)
+    }
+
+    /// Écrit `value` dans l'entrée FAT de `cluster`, dans chaque copie de la
+    /// FAT. Les 4 bits de poids fort, réservés, sont conservés.
+    pub fn write_fat_entry(&mut self, cluster: u32, value: u32) {
+        let offset = cluster * 4;
+        let idx = (offset % 512) as usize;
+        let mut buf = [0u8; 512];
+        for fat in 0..self.boot_sector.fats as u32 {
+            let sector = self.boot_sector.reserved_sectors as u32
+                + fat * self.boot_sector.sectors_per_fat
+                + offset / 512;
+            self.device.read_sector(sector, &mut buf);
+            let old = u32::from_le_bytes([buf[idx], buf[idx + 1], buf[idx + 2], buf[idx + 3]]);
+            let new = (old & 0xF000_0000) | (value & 0x0FFF_FFFF);
+            buf[idx..idx + 4].copy_from_slice(&new.to_le_bytes());
+            self.device.write_sector(sector, &buf);
+        }
+    }
+
+    /// Lit les secteurs du cluster dans `buf`, au plus `cluster_size()` octets.
     pub fn read_cluster(&mut self, cluster: u32, buf: &mut [u8]) {
         let lba = self.cluster_to_lba(cluster);
+        let sectors = self.boot_sector.sectors_per_cluster as usize;
         let mut tmp = [0u8; 512];
-        self.device.read_sector(lba, &mut tmp);
-        buf[..512].copy_from_slice(&tmp);
+        for (i, chunk) in buf.chunks_mut(512).take(sectors).enumerate() {
+            self.device.read_sector(lba + i as u32, &mut tmp);
+            chunk.copy_from_slice(&tmp[..chunk.len()]);
+        }
     }
 
-    fn read_cluster_chain(&mut self, start: u32) -> Result<Vec<u8>, ()> {
-        if start < 2 {
-            panic!("invalid cluster {}", start);
+    /// Écrit `buf` dans les secteurs du cluster, complété par des zéros.
+    pub fn write_cluster(&mut self, cluster: u32, buf: &[u8]) {
+        let lba = self.cluster_to_lba(cluster);
+        let sectors = self.boot_sector.sectors_per_cluster as usize;
+        let mut chunks = buf.chunks(512);
+        for i in 0..sectors {
+            let mut tmp = [0u8; 512];
+            if let Some(chunk) = chunks.next() {
+                tmp[..chunk.len()].copy_from_slice(chunk);
+            }
+            self.device.write_sector(lba + i as u32, &tmp);
         }
-        let cluster_size = self.cluster_size();
+    }
+
+    /// Clusters de la chaîne commençant à `start`, dans l'ordre.
+    ///
+    /// Une chaîne qui sort du volume ou qui boucle est refusée.
+    fn cluster_chain(&mut self, start: u32) -> Result<Vec<u32>, FatError> {
+        let last = self.cluster_count() + 1;
+        let mut chain = Vec::new();
         let mut current = start;
-        let mut data = Vec::new();
         loop {
-            let mut buf = vec![0u8; cluster_size];
-            self.read_cluster(current, &mut buf);
-            data.extend_from_slice(&buf);
-            let next = self.read_fat_entry(current);
-            if next >= 0x0FFF_FFF8 {
+            if !(2..=last).contains(&current) || chain.len() > last as usize {
+                return Err(FatError::InvalidCluster);
+            }
+            chain.push(current);
+            current = self.read_fat_entry(current);
+            if current >= 0x0FFF_FFF8 {
+                return Ok(chain);
+            }
+        }
+    }
+
+    /// Clusters du répertoire commençant au cluster `dir`. Le cluster 0, que
+    /// porte `..` dans les sous-répertoires de la racine, désigne la racine.
+    ///
+    /// Tout autre répertoire commence par son entrée `.`, qui pointe sur
+    /// lui-même : un cluster qui n'en a pas (fichier, cluster quelconque) est
+    /// refusé par `NotADirectory`.
+    fn directory_chain(&mut self, dir: u32) -> Result<Vec<u32>, FatError> {
+        let root = self.boot_sector.root_cluster;
+        let start = if dir == 0 { root } else { dir };
+        let chain = self.cluster_chain(start)?;
+        if start != root {
+            let mut buf = vec![0u8; self.cluster_size()];
+            self.read_cluster(start, &mut buf);
+            let dot = DirectoryEntry::parse(&buf[..ENTRY_SIZE]);
+            if dot.name != *b".          " || !dot.is_directory() || dot.first_cluster != start {
+                return Err(FatError::NotADirectory);
+            }
+        }
+        Ok(chain)
+    }
+
+    /// Cherche `count` clusters libres, sans les réserver.
+    fn free_clusters(&mut self, count: usize) -> Result<Vec<u32>, FatError> {
+        let mut free = Vec::new();
+        for cluster in 2..self.cluster_count() + 2 {
+            if free.len() == count {
                 break;
             }
-            if next < 2 {
-                panic!("invalid FAT entry {}", next);
+            if self.read_fat_entry(cluster) == FREE_CLUSTER {
+                free.push(cluster);
             }
-            current = next;
+        }
+        if free.len() < count {
+            return Err(FatError::DiskFull);
+        }
+        Ok(free)
+    }
+
+    /// Contenu des clusters de la chaîne commençant à `start` (voir
+    /// `cluster_chain`).
+    fn read_cluster_chain(&mut self, start: u32) -> Result<Vec<u8>, FatError> {
+        let cluster_size = self.cluster_size();
+        let chain = self.cluster_chain(start)?;
+        let mut data = vec![0u8; chain.len() * cluster_size];
+        for (cluster, buf) in chain.into_iter().zip(data.chunks_mut(cluster_size)) {
+            self.read_cluster(cluster, buf);
         }
         Ok(data)
     }
 
     pub fn read_root_directory(&mut self) -> Result<Vec<DirectoryEntry>, FatError> {
-        let data = self
-            .read_cluster_chain(self.boot_sector.root_cluster)
-            .map_err(|_| FatError::Io)?;
+        self.read_directory(self.boot_sector.root_cluster)
+    }
+
+    /// Lit les entrées du répertoire commençant au cluster `cluster`, `.` et
+    /// `..` compris (0 désigne la racine, voir `directory_chain`).
+    ///
+    /// Les entrées de nom long ne sont pas retournées : elles renseignent le
+    /// `long_name` de l'entrée courte qui les suit.
+    pub fn read_directory(&mut self, cluster: u32) -> Result<Vec<DirectoryEntry>, FatError> {
         let mut entries = Vec::new();
-        for chunk in data.chunks(32) {
-            if chunk[0] == 0x00 { break; }
-            if chunk[0] == 0xE5 { continue; }
-            let mut name = [0u8; 11];
-            name.copy_from_slice(&chunk[0..11]);
-            let attr = chunk[11];
-            let first_cluster =
-                ((u16::from_le_bytes([chunk[20], chunk[21]]) as u32) << 16)
-                | u16::from_le_bytes([chunk[26], chunk[27]]) as u32;
-            let size = u32::from_le_bytes([
-                chunk[28], chunk[29], chunk[30], chunk[31]
-            ]);
-            entries.push(DirectoryEntry { name, attr, first_cluster, size });
+        let mut long = None;
+        let mut buf = vec![0u8; self.cluster_size()];
+        for cluster in self.directory_chain(cluster)? {
+            self.read_cluster(cluster, &mut buf);
+            for raw in buf.chunks(ENTRY_SIZE) {
+                if raw[0] == 0x00 {
+                    return Ok(entries);
+                }
+                if raw[0] == DELETED_ENTRY {
+                    long = None;
+                } else if is_long_name(raw) {
+                    long = LongName::push(long.take(), raw);
+                } else {
+                    let mut entry = DirectoryEntry::parse(raw);
+                    entry.long_name = long.take().and_then(|long| long.finish(&entry.name));
+                    entries.push(entry);
+                }
+            }
         }
         Ok(entries)
     }
 
+    /// Résout un chemin absolu (`/BOOT/CONFIG/KERNEL.CFG`) depuis la racine.
+    ///
+    /// `.` et `..` sont interprétés sans lire le disque ; `..` depuis la
+    /// racine reste à la racine. Le chemin `/` donne une entrée fictive pour
+    /// la racine, qui n'a pas d'entrée sur le disque.
+    pub fn lookup(&mut self, path: &str) -> Result<DirectoryEntry, FatError> {
+        let root = DirectoryEntry {
+            name: [b' '; 11],
+            attr: ATTR_DIRECTORY,
+            first_cluster: self.boot_sector.root_cluster,
+            size: 0,
+            long_name: None,
+        };
+        let mut ancestors = vec![root];
+        for component in path.split('/').filter(|component| !component.is_empty()) {
+            let current = &ancestors[ancestors.len() - 1];
+            if !current.is_directory() {
+                return Err(FatError::NotADirectory);
+            }
+            match component {
+                "." => {}
+                ".." => {
+                    if ancestors.len() > 1 {
+                        ancestors.pop();
+                    }
+                }
+                name => {
+                    let cluster = current.first_cluster;
+                    let entry = self.child(cluster, name)?;
+                    ancestors.push(entry);
+                }
+            }
+        }
+        Ok(ancestors.pop().unwrap())
+    }
+
+    /// Entrée du répertoire `dir` dont le nom court ou le nom long est
+    /// `name`, sans tenir compte de la casse.
+    fn child(&mut self, dir: u32, name: &str) -> Result<DirectoryEntry, FatError> {
+        // Un nom qui n'est pas un nom 8.3 valide ne peut être qu'un nom long.
+        let short = short_name(name).ok();
+        let lower = name.to_lowercase();
+        self.read_directory(dir)?
+            .into_iter()
+            .find(|entry| {
+                entry.attr & ATTR_VOLUME_ID == 0
+                    && (Some(entry.name) == short
+                        || entry.long_name.as_ref().is_some_and(|long| long.to_lowercase() == lower))
+            })
+            .ok_or(FatError::NotFound)
+    }
+
+    /// Lit le fichier désigné par le chemin absolu `path` (voir `lookup`).
+    pub fn open_path(&mut self, path: &str) -> Result<Vec<u8>, FatError> {
+        let entry = self.lookup(path)?;
+        if entry.is_directory() {
+            return Err(FatError::IsADirectory);
+        }
+        self.open_file(&entry)
+    }
+
+    /// Crée dans le répertoire commençant au cluster `dir` un fichier vide
+    /// nommé `name`.
+    ///
+    /// Un nom 8.3 valide est enregistré tel quel, en majuscules. Tout autre
+    /// nom devient un nom long VFAT, suivi d'un alias court unique
+    /// (`kernel-config.toml` : `KERNEL~1.TOM`). Le répertoire est agrandi
+    /// s'il n'a pas assez d'entrées libres consécutives. `dir` doit désigner
+    /// la racine (ou 0) ou un répertoire, sinon `NotADirectory`.
+    pub fn create_file(&mut self, dir: u32, name: &str) -> Result<DirectoryEntry, FatError> {
+        match self.child(dir, name) {
+            Ok(_) => return Err(FatError::AlreadyExists),
+            Err(FatError::NotFound) => {}
+            Err(error) => return Err(error),
+        }
+        let (short, long_name) = match short_name(name) {
+            Ok(short) => (short, None),
+            Err(_) => {
+                check_long_name(name)?;
+                (self.alias(dir, name)?, Some(String::from(name)))
+            }
+        };
+
+        let mut raws = match &long_name {
+            Some(long) => long_name_entries(long, &short),
+            None => Vec::new(),
+        };
+        let mut raw = [0u8; ENTRY_SIZE];
+        raw[0..11].copy_from_slice(&short);
+        raw[11] = ATTR_ARCHIVE;
+        raws.push(raw);
+        let slots = self.free_slots(dir, raws.len())?;
+        for (slot, raw) in slots.into_iter().zip(&raws) {
+            self.write_slot(slot, raw);
+        }
+        Ok(DirectoryEntry { name: short, attr: ATTR_ARCHIVE, first_cluster: 0, size: 0, long_name })
+    }
+
+    /// Alias court `BASE~N.EXT` du nom long `name`, avec le plus petit `N`
+    /// qui n'est pas déjà pris dans le répertoire `dir`.
+    fn alias(&mut self, dir: u32, name: &str) -> Result<[u8; 11], FatError> {
+        let taken: Vec<[u8; 11]> = self.read_directory(dir)?.iter().map(|entry| entry.name).collect();
+        // Caractères hors du jeu 8.3 remplacés par `_`, espaces et points retirés.
+        let convert = |part: &str| -> Vec<u8> {
+            part.chars()
+                .filter(|&c| c != ' ' && c != '.')
+                .map(|c| match c {
+                    'a'..='z' | 'A'..='Z' | '0'..='9' => c.to_ascii_uppercase() as u8,
+                    c if c.is_ascii() && "!#$%&'()-@^_`{}~".contains(c) => c as u8,
+                    _ => b'_',
+                })
+                .collect()
+        };
+        let trimmed = name.trim_start_matches('.');
+        let (base, ext) = match trimmed.rfind('.') {
+            Some(dot) => (convert(&trimmed[..dot]), convert(&trimmed[dot + 1..])),
+            None => (convert(trimmed), Vec::new()),
+        };
+        let base = if base.is_empty() { vec![b'_'] } else { base };
+
+        for n in 1..1_000_000u32 {
+            let tail = format!("~{}", n);
+            let keep = base.len().min(8 - tail.len());
+            let mut short = [b' '; 11];
+            short[..keep].copy_from_slice(&base[..keep]);
+            short[keep..keep + tail.len()].copy_from_slice(tail.as_bytes());
+            let ext = &ext[..ext.len().min(3)];
+            short[8..8 + ext.len()].copy_from_slice(ext);
+            if !taken.contains(&short) {
+                return Ok(short);
+            }
+        }
+        Err(FatError::AlreadyExists)
+    }
+
+    /// Remplace le contenu du fichier `entry` du répertoire `dir` par `data`.
+    ///
+    /// La chaîne existante est réutilisée, prolongée par des clusters libres
+    /// ou raccourcie, puis l'entrée de répertoire (premier cluster, taille)
+    /// et `entry` sont mises à jour. En cas de `DiskFull`, rien n'est modifié.
+    /// Un répertoire ne peut pas être écrit ainsi (`IsADirectory`), et `dir`
+    /// doit être un répertoire, comme pour `create_file`.
+    pub fn write_file(
+        &mut self,
+        dir: u32,
+        entry: &mut DirectoryEntry,
+        data: &[u8],
+    ) -> Result<(), FatError> {
+        if entry.is_directory() {
+            return Err(FatError::IsADirectory);
+        }
+        let slots = self.find_entry(dir, &entry.name)?.ok_or(FatError::NotFound)?;
+        let slot = slots[slots.len() - 1];
+        if data.len() > u32::MAX as usize {
+            return Err(FatError::DiskFull);
+        }
+        let cluster_size = self.cluster_size();
+        let needed = data.len().div_ceil(cluster_size);
+        let mut chain = match entry.first_cluster {
+            0 => Vec::new(),
+            first => self.cluster_chain(first)?,
+        };
+
+        if chain.len() < needed {
+            let free = self.free_clusters(needed - chain.len())?;
+            for &cluster in &free {
+                if let Some(&last) = chain.last() {
+                    self.write_fat_entry(last, cluster);
+                }
+                self.write_fat_entry(cluster, END_OF_CHAIN);
+                chain.push(cluster);
+            }
+        } else if chain.len() > needed {
+            for &cluster in &chain[needed..] {
+                self.write_fat_entry(cluster, FREE_CLUSTER);
+            }
+            chain.truncate(needed);
+            if let Some(&last) = chain.last() {
+                self.write_fat_entry(last, END_OF_CHAIN);
+            }
+        }
+
+        for (&cluster, chunk) in chain.iter().zip(data.chunks(cluster_size)) {
+            self.write_cluster(cluster, chunk);
+        }
+
+        entry.first_cluster = chain.first().copied().unwrap_or(0);
+        entry.size = data.len() as u32;
+        let mut raw = self.read_slot(slot);
+        raw[20..22].copy_from_slice(&((entry.first_cluster >> 16) as u16).to_le_bytes());
+        raw[26..28].copy_from_slice(&(entry.first_cluster as u16).to_le_bytes());
+        raw[28..32].copy_from_slice(&entry.size.to_le_bytes());
+        self.write_slot(slot, &raw);
+        Ok(())
+    }
+
+    /// Supprime le fichier ou le répertoire vide désigné par `path`.
+    ///
+    /// L'entrée courte et les entrées de nom long qui la précèdent sont
+    /// marquées supprimées, puis les clusters de la chaîne sont libérés dans
+    /// toutes les copies de la FAT.
+    pub fn remove(&mut self, path: &str) -> Result<(), FatError> {
+        let (dir, name) = self.parent_of(path)?;
+        let name = self.child(dir, name)?.name;
+        let slots = self.find_entry(dir, &name)?.ok_or(FatError::NotFound)?;
+        let entry = DirectoryEntry::parse(&self.read_slot(slots[slots.len() - 1]));
+        let chain = match entry.first_cluster {
+            0 => Vec::new(),
+            first => self.cluster_chain(first)?,
+        };
+        if entry.is_directory() && !self.is_empty_directory(&chain) {
+            return Err(FatError::DirectoryNotEmpty);
+        }
+
+        for &slot in &slots {
+            let mut raw = self.read_slot(slot);
+            raw[0] = DELETED_ENTRY;
+            self.write_slot(slot, &raw);
+        }
+        for cluster in chain {
+            self.write_fat_entry(cluster, FREE_CLUSTER);
+        }
+        Ok(())
+    }
+
+    /// Premier cluster du répertoire parent et dernier composant de `path`.
+    fn parent_of<'p>(&mut self, path: &'p str) -> Result<(u32, &'p str), FatError> {
+        let path = path.trim_end_matches('/');
+        let (parent, name) = path.rsplit_once('/').unwrap_or(("", path));
+        let parent = self.lookup(parent)?;
+        if !parent.is_directory() {
+            return Err(FatError::NotADirectory);
+        }
+        Ok((parent.first_cluster, name))
+    }
+
+    /// Indique si le répertoire formé des clusters `chain` ne contient que
+    /// `.` et `..`.
+    fn is_empty_directory(&mut self, chain: &[u32]) -> bool {
+        let mut buf = vec![0u8; self.cluster_size()];
+        for &cluster in chain {
+            self.read_cluster(cluster, &mut buf);
+            for raw in buf.chunks(ENTRY_SIZE) {
+                if raw[0] == 0x00 {
+                    return true;
+                }
+                // Seules les entrées `.` et `..` commencent par un point.
+                if raw[0] != DELETED_ENTRY && !is_long_name(raw) && raw[0] != b'.' {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Positions (cluster, décalage) des entrées de l'objet nommé `name`
+    /// dans le répertoire commençant au cluster `dir` : ses entrées de nom
+    /// long éventuelles, puis son entrée courte, toujours en dernier.
+    fn find_entry(
+        &mut self,
+        dir: u32,
+        name: &[u8; 11],
+    ) -> Result<Option<Vec<(u32, usize)>>, FatError> {
+        let mut buf = vec![0u8; self.cluster_size()];
+        let mut slots = Vec::new();
+        for cluster in self.directory_chain(dir)? {
+            self.read_cluster(cluster, &mut buf);
+            for (i, raw) in buf.chunks(ENTRY_SIZE).enumerate() {
+                if raw[0] == 0x00 {
+                    return Ok(None);
+                }
+                if raw[0] == DELETED_ENTRY {
+                    slots.clear();
+                    continue;
+                }
+                slots.push((cluster, i * ENTRY_SIZE));
+                if is_long_name(raw) {
+                    continue;
+                }
+                if raw[0..11] == name[..] {
+                    return Ok(Some(slots));
+                }
+                slots.clear();
+            }
+        }
+        Ok(None)
+    }
+
+    /// Premières `count` entrées libres (jamais utilisées ou supprimées)
+    /// consécutives du répertoire `dir`, éventuellement à cheval sur deux
+    /// clusters. À défaut, les entrées libres de la fin du répertoire sont
+    /// complétées par de nouveaux clusters vides.
+    fn free_slots(&mut self, dir: u32, count: usize) -> Result<Vec<(u32, usize)>, FatError> {
+        let chain = self.directory_chain(dir)?;
+        let mut run = Vec::new();
+        let mut buf = vec![0u8; self.cluster_size()];
+        for &cluster in &chain {
+            self.read_cluster(cluster, &mut buf);
+            for (i, raw) in buf.chunks(ENTRY_SIZE).enumerate() {
+                if raw[0] != 0x00 && raw[0] != DELETED_ENTRY {
+                    run.clear();
+                    continue;
+                }
+                run.push((cluster, i * ENTRY_SIZE));
+                if run.len() == count {
+                    return Ok(run);
+                }
+            }
+        }
+
+        let per_cluster = self.cluster_size() / ENTRY_SIZE;
+        let mut last = chain[chain.len() - 1];
+        for cluster in self.free_clusters((count - run.len()).div_ceil(per_cluster))? {
+            self.write_cluster(cluster, &[]);
+            self.write_fat_entry(cluster, END_OF_CHAIN);
+            self.write_fat_entry(last, cluster);
+            run.extend((0..per_cluster).map(|i| (cluster, i * ENTRY_SIZE)));
+            last = cluster;
+        }
+        run.truncate(count);
+        Ok(run)
+    }
+
+    fn read_slot(&mut self, (cluster, offset): (u32, usize)) -> [u8; ENTRY_SIZE] {
+        let mut buf = [0u8; 512];
+        self.device.read_sector(self.cluster_to_lba(cluster) + (offset / 512) as u32, &mut buf);
+        let idx = offset % 512;
+        let mut raw = [0u8; ENTRY_SIZE];
+        raw.copy_from_slice(&buf[idx..idx + ENTRY_SIZE]);
+        raw
+    }
+
+    fn write_slot(&mut self, (cluster, offset): (u32, usize), raw: &[u8; ENTRY_SIZE]) {
+        let lba = self.cluster_to_lba(cluster) + (offset / 512) as u32;
+        let mut buf = [0u8; 512];
+        self.device.read_sector(lba, &mut buf);
+        let idx = offset % 512;
+        buf[idx..idx + ENTRY_SIZE].copy_from_slice(raw);
+        self.device.write_sector(lba, &buf);
+    }
+
     pub fn open_file(&mut self, entry: &DirectoryEntry) -> Result<Vec<u8>, FatError> {
-        let mut data = self
-            .read_cluster_chain(entry.first_cluster)
-            .map_err(|_| FatError::Io)?;
+        // Un fichier vide n'a pas de chaîne de clusters (premier cluster 0).
+        if entry.first_cluster == 0 {
+            return Ok(Vec::new());
+        }
+        let mut data = self.read_cluster_chain(entry.first_cluster)?;
         data.truncate(entry.size as usize);
         Ok(data)
     }
 }
 
+/// Indique si l'entrée `raw` est une entrée de nom long, bits réservés de
+/// l'attribut ignorés.
+fn is_long_name(raw: &[u8]) -> bool {
+    raw[11] & ATTR_MASK == ATTR_LONG_NAME
+}
+
+/// Somme de contrôle d'un nom court, recopiée dans chacune de ses entrées
+/// de nom long.
+fn lfn_checksum(name: &[u8; 11]) -> u8 {
+    name.iter().fold(0u8, |sum, &b| sum.rotate_right(1).wrapping_add(b))
+}
+
+/// Nom long en cours de lecture : les entrées sont rangées de la dernière
+/// partie du nom à la première, chacune portant son numéro de séquence.
+struct LongName {
+    /// Caractères UCS-2 lus jusqu'ici, dans l'ordre du nom.
+    units: Vec<u16>,
+    /// Numéro de séquence attendu pour l'entrée suivante (0 : complet).
+    next: u8,
+    /// Somme de contrôle du nom court, identique dans toutes les entrées.
+    checksum: u8,
+}
+
+impl LongName {
+    /// Ajoute l'entrée de nom long `raw` à la séquence `long`. Une séquence
+    /// incohérente est abandonnée (`None`).
+    fn push(long: Option<Self>, raw: &[u8]) -> Option<Self> {
+        let units = LFN_OFFSETS.iter().map(|&at| u16::from_le_bytes([raw[at], raw[at + 1]]));
+        if raw[0] & LFN_LAST != 0 {
+            let count = raw[0] & !LFN_LAST;
+            if count == 0 || count > LFN_MAX_ENTRIES {
+                return None;
+            }
+            return Some(LongName { units: units.collect(), next: count - 1, checksum: raw[13] });
+        }
+        let mut long = long?;
+        if long.next == 0 || raw[0] != long.next || raw[13] != long.checksum {
+            return None;
+        }
+        long.units.splice(0..0, units);
+        long.next -= 1;
+        Some(long)
+    }
+
+    /// Nom décodé, si la séquence est complète et décrit le nom court `name`.
+    fn finish(self, name: &[u8; 11]) -> Option<String> {
+        if self.next != 0 || self.checksum != lfn_checksum(name) {
+            return None;
+        }
+        let end = self.units.iter().position(|&unit| unit == 0).unwrap_or(self.units.len());
+        Some(
+            char::decode_utf16(self.units[..end].iter().copied())
+                .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect(),
+        )
+    }
+}
+
+/// Vérifie que `name` peut servir de nom long : 255 caractères UCS-2 au
+/// plus, sans caractère de contrôle ni `"*/:<>?\|`, ni point ou espace final.
+fn check_long_name(name: &str) -> Result<(), FatError> {
+    let invalid = |c: char| c < ' ' || "\"*/:<>?\\|".contains(c);
+    if name.is_empty()
+        || name.encode_utf16().count() > 255
+        || name.ends_with('.')
+        || name.ends_with(' ')
+        || name.chars().any(invalid)
+    {
+        return Err(FatError::InvalidName);
+    }
+    Ok(())
+}
+
+/// Entrées de nom long de `name` pour le nom court `short`, dans l'ordre où
+/// elles précèdent l'entrée courte sur le disque (fin du nom en premier).
+fn long_name_entries(name: &str, short: &[u8; 11]) -> Vec<[u8; ENTRY_SIZE]> {
+    let mut units: Vec<u16> = name.encode_utf16().collect();
+    // Un nom qui ne remplit pas la dernière entrée est terminé par un
+    // caractère nul puis complété par des 0xFFFF.
+    if !units.len().is_multiple_of(LFN_OFFSETS.len()) {
+        units.push(0);
+        units.resize(units.len().div_ceil(LFN_OFFSETS.len()) * LFN_OFFSETS.len(), 0xFFFF);
+    }
+    let count = units.len() / LFN_OFFSETS.len();
+    let checksum = lfn_checksum(short);
+    units
+        .chunks(LFN_OFFSETS.len())
+        .enumerate()
+        .rev()
+        .map(|(i, chars)| {
+            let mut raw = [0u8; ENTRY_SIZE];
+            raw[0] = (i + 1) as u8 | if i + 1 == count { LFN_LAST } else { 0 };
+            raw[11] = ATTR_LONG_NAME;
+            raw[13] = checksum;
+            for (&at, unit) in LFN_OFFSETS.iter().zip(chars) {
+                raw[at..at + 2].copy_from_slice(&unit.to_le_bytes());
+            }
+            raw
+        })
+        .collect()
+}
+
+/// Convertit `name` (`NOM.EXT`, 8 + 3 caractères au plus) en nom court de
+/// 11 octets complété par des espaces, en majuscules.
+fn short_name(name: &str) -> Result<[u8; 11], FatError> {
+    let (base, ext) = match name.rfind('.') {
+        Some(dot) => (&name[..dot], &name[dot + 1..]),
+        None => (name, ""),
+    };
+    let valid = |part: &str, max: usize| {
+        part.len() <= max
+            && part.bytes().all(|b| b.is_ascii_alphanumeric() || b"!#$%&'()-@^_`{}~".contains(&b))
+    };
+    if base.is_empty() || !valid(base, 8) || !valid(ext, 3) {
+        return Err(FatError::InvalidName);
+    }
+    let mut short = [b' '; 11];
+    short[..base.len()].copy_from_slice(base.as_bytes());
+    short[8..8 + ext.len()].copy_from_slice(ext.as_bytes());
+    short.make_ascii_uppercase();
+    Ok(short)
+}
+
 pub struct MemoryDisk {
     data: [u8; 4096],
 }
@@ -184,6 +831,7 @@ impl MemoryDisk {
         data[13] = 1; // sectors per cluster
         data[14..16].copy_from_slice(&1u16.to_le_bytes()); // reserved sectors
         data[16] = 2; // number of FATs
+        data[19..21].copy_from_slice(&8u16.to_le_bytes()); // total sectors
         data[36..40].copy_from_slice(&1u32.to_le_bytes()); // sectors per FAT
         data[44..48].copy_from_slice(&2u32.to_le_bytes()); // root cluster = 2
 
@@ -10,9 +10,11 @@
 #![cfg_attr(test, no_main)]
 #![feature(custom_test_frameworks)]
 #![test_runner(crate::test_runner)]
+#![reexport_test_harness_main = "test_main"]
 
-// On active alloc_error_handler **seulement** si on n'est PAS en oom_integration.
-#![cfg_attr(not(feature = "oom_integration"), feature(alloc_error_handler))]
+// On active alloc_error_handler **seulement** si on n'est PAS en oom_integration
+// (et sur la cible du noyau, comme `allocator::on_oom`).
+#![cfg_attr(all(target_os = "none", not(feature = "oom_integration")), feature(alloc_error_handler))]
 
 extern crate alloc;
 
@@ -32,22 +34,98 @@ pub mod vga_buffer;
 pub mod allocator;
 pub mod fat32;
 
-use crate::allocator::SimpleAllocator;
+use bootloader::bootinfo::{BootInfo, MemoryRegionType};
+
+#[cfg(all(
+    feature = "buddy",
+    any(
+        feature = "slab_debug",
+        feature = "alloc_trace",
+        feature = "fault_injection",
+        feature = "magazines",
+        feature = "kasan"
+    )
+))]
+compile_error!("slab_debug, alloc_trace, fault_injection, magazines and kasan instrument SimpleAllocator and cannot be combined with buddy");
+
+/// Allocateur global : slab allocator par défaut, buddy avec la feature `buddy`.
+#[cfg(not(feature = "buddy"))]
+pub type KernelAllocator = allocator::SimpleAllocator;
+/// Allocateur global : slab allocator par défaut, buddy avec la feature `buddy`.
+#[cfg(feature = "buddy")]
+pub type KernelAllocator = allocator::BuddyAllocator;
 
 #[global_allocator]
-static ALLOCATOR: SimpleAllocator = SimpleAllocator::new();
+pub static ALLOCATOR: KernelAllocator = KernelAllocator::new();
+
+/// Taille maximale du heap prélevé dans la carte mémoire du bootloader (1 MiB).
+pub const KERNEL_HEAP_MAX: usize = 1024 * 1024;
+
+/// Initialise l'allocateur global sur la plus grande région `Usable` de la
+/// carte mémoire fournie par le bootloader (au plus `KERNEL_HEAP_MAX` octets).
+///
+/// La région est adressée à travers le mapping complet de la mémoire physique
+/// (`physical_memory_offset`). Le CPU de démarrage est enregistré comme CPU 0
+/// (`allocator::init_cpu`). Les appels suivants sont sans effet.
+pub fn init_heap(boot_info: &'static BootInfo) {
+    let region = boot_info
+        .memory_map
+        .iter()
+        .filter(|r| r.region_type == MemoryRegionType::Usable)
+        .max_by_key(|r| r.range.end_addr() - r.range.start_addr());
+    if let Some(region) = region {
+        let start = boot_info.physical_memory_offset + region.range.start_addr();
+        let size = (region.range.end_addr() - region.range.start_addr()) as usize;
+        unsafe {
+            allocator::init_cpu(0);
+            ALLOCATOR.init(start as usize, size.min(KERNEL_HEAP_MAX));
+        }
+    }
+}
 
 /// Test runner global (doit être visible des tests d’intégration)
+///
+/// Chaque crate de test le déclare avec `#![test_runner(blog_os::test_runner)]`
+/// et `#![reexport_test_harness_main = "test_main"]`, puis appelle sa propre
+/// `test_main()`. Une fois tous les tests passés, QEMU est quitté avec
+/// `QemuExitCode::Success` ; un échec passe par `test_panic_handler`.
 pub fn test_runner(tests: &[&dyn Fn()]) {
+    serial_println!("Running {} tests", tests.len());
     for test in tests {
         test();
     }
+    serial_println!("[ok]");
+    exit_qemu(QemuExitCode::Success);
 }
 
-#[no_mangle]
-pub extern "C" fn test_main() {
-    let tests: &[&dyn Fn()] = &[];
-    test_runner(tests);
+/// Variante de `test_runner` qui vérifie que chaque test rend toute la
+/// mémoire qu'il alloue (`#![test_runner(blog_os::leak_checking_test_runner)]`).
+///
+/// Chaque test passe par `run_checking_leaks` ; une fois tous les tests
+/// passés, QEMU est quitté avec `QemuExitCode::Success`.
+pub fn leak_checking_test_runner(tests: &[&dyn Fn()]) {
+    serial_println!("Running {} tests", tests.len());
+    for test in tests {
+        run_checking_leaks(*test);
+    }
+    serial_println!("[ok]");
+    exit_qemu(QemuExitCode::Success);
+}
+
+/// Exécute `test` et panique s'il laisse des blocs vivants.
+///
+/// Un instantané du heap est pris avant le test ; si une classe de taille
+/// compte plus de blocs vivants après, le panic liste les blocs perdus par
+/// classe (et, avec `alloc_trace`, leurs adresses et appelants).
+pub fn run_checking_leaks(test: &dyn Fn()) {
+    let before = ALLOCATOR.snapshot();
+    test();
+    let leaks = ALLOCATOR.snapshot().leaks_since(&before);
+    if !leaks.is_empty() {
+        #[cfg(feature = "alloc_trace")]
+        ALLOCATOR.dump_leaked_blocks(&before);
+        panic!("heap leak: {}", leaks);
+    }
 }
 
 pub fn test_panic_handler(info: &PanicInfo) -> ! {
@@ -81,13 +159,6 @@ pub enum QemuExitCode {
     Failed  = 0x11,
 }
 
-/// Handler global d’OOM, **désactivé** quand feature = "oom_integration".
-#[cfg(all(feature = "alloc", not(feature = "oom_integration")))]
-//#[alloc_error_handler]
-//fn on_oom(layout: alloc::alloc::Layout) -> ! {
-//    panic!("Out of memory: {:?}", layout);
-//}
-
 /// Point d'entrée pour `cargo xtest`.
 #[cfg(test)]
 #[no_mangle]
@@ -6,11 +6,14 @@
 use blog_os::println;
 use blog_os::fat32::{Fat32, MemoryDisk};
 use blog_os::fat32_checks;
+use bootloader::{entry_point, BootInfo};
 use core::panic::PanicInfo;
 
-#[no_mangle]
-pub extern "C" fn _start() -> ! {
+entry_point!(kernel_main);
+
+fn kernel_main(boot_info: &'static BootInfo) -> ! {
     println!("Hello World{}", "!");
+    blog_os::init_heap(boot_info);
 
     let disk = MemoryDisk::new();
     match Fat32::new(disk) {
@@ -0,0 +1,59 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(blog_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate blog_os;
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    blog_os::init_heap(boot_info);
+    test_main();
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    blog_os::test_panic_handler(info)
+}
+
+extern crate alloc;
+
+#[test_case]
+fn every_alignment_up_to_a_page() {
+    let mut align = 1;
+    while align <= blog_os::allocator::PAGE_SIZE {
+        for &size in &[1, 16, align, 100, 3000] {
+            let layout = alloc::alloc::Layout::from_size_align(size, align).unwrap();
+            let ptr = unsafe { alloc::alloc::alloc(layout) };
+            assert!(!ptr.is_null(), "alloc failed: {:?}", layout);
+            assert_eq!(ptr as usize % align, 0, "misaligned: {:?} -> {:p}", layout, ptr);
+            unsafe { alloc::alloc::dealloc(ptr, layout) };
+        }
+        align *= 2;
+    }
+}
+
+#[test_case]
+fn over_aligned_box() {
+    #[repr(align(64))]
+    struct PerCpu([u8; 16]);
+
+    let value = alloc::boxed::Box::new(PerCpu([7; 16]));
+    assert_eq!(&*value as *const PerCpu as usize % 64, 0);
+    assert_eq!(value.0[15], 7);
+}
+
+#[test_case]
+fn page_allocation() {
+    let pages = unsafe { blog_os::ALLOCATOR.alloc_pages(2) };
+    assert!(!pages.is_null());
+    assert_eq!(pages as usize % blog_os::allocator::PAGE_SIZE, 0);
+    unsafe { blog_os::ALLOCATOR.dealloc_pages(pages, 2) };
+}
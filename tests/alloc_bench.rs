@@ -0,0 +1,60 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(blog_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate blog_os;
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    blog_os::init_heap(boot_info);
+    test_main();
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    blog_os::test_panic_handler(info)
+}
+
+extern crate alloc;
+
+mod bench;
+
+use blog_os::ALLOCATOR;
+
+/// Nombre de cycles alloc/free mesurés par taille.
+const ROUNDS: u64 = 10_000;
+
+#[test_case]
+fn alloc_free_cycles() {
+    let before = bench::LockedInit::<_, { blog_os::allocator::N_SLABS }>::new(&ALLOCATOR);
+    bench::write_table(&before, &ALLOCATOR, ROUNDS, &mut *blog_os::serial::SERIAL1.lock()).unwrap();
+}
+
+/// Charge mixte identique pour les deux allocateurs : 256 blocs de tailles
+/// pseudo-aléatoires dont un sur deux est libéré, puis état du heap.
+#[test_case]
+fn mixed_workload_fragmentation() {
+    let mut seed = 0x2545_F491u32;
+    let mut blocks = alloc::vec::Vec::with_capacity(256);
+    for _ in 0..256 {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        blocks.push(alloc::vec![0u8; 1 + (seed % 2048) as usize]);
+    }
+    let mut index = 0;
+    blocks.retain(|_| {
+        index += 1;
+        index % 2 == 0
+    });
+    blog_os::serial_println!("bench: allocator {}", core::any::type_name::<blog_os::KernelAllocator>());
+    ALLOCATOR.print_stats();
+}
@@ -0,0 +1,111 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(blog_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate blog_os;
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    blog_os::init_heap(boot_info);
+    test_main();
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    blog_os::test_panic_handler(info)
+}
+
+extern crate alloc;
+
+/// Ligne de texte de taille fixe : le puits de `write_trace` ne doit pas
+/// allouer, la trace étant verrouillée pendant l'écriture.
+struct Line {
+    buf: [u8; 256],
+    len: usize,
+}
+
+impl Line {
+    const fn new() -> Self {
+        Line { buf: [0; 256], len: 0 }
+    }
+
+    fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.buf[..self.len]).unwrap()
+    }
+}
+
+impl core::fmt::Write for Line {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let end = self.len + s.len();
+        if end > self.buf.len() {
+            return Err(core::fmt::Error);
+        }
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Compte les lignes de la trace contenant `needle`.
+struct Finder<'a> {
+    needle: &'a str,
+    line: Line,
+    lines: usize,
+    found: usize,
+}
+
+impl core::fmt::Write for Finder<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for c in s.chars() {
+            if c == '\n' {
+                self.lines += 1;
+                if self.line.as_str().contains(self.needle) {
+                    self.found += 1;
+                }
+                self.line.len = 0;
+            } else {
+                self.line.write_char(c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn count_lines(needle: &str) -> (usize, usize) {
+    let mut finder = Finder { needle, line: Line::new(), lines: 0, found: 0 };
+    blog_os::ALLOCATOR.write_trace(&mut finder).unwrap();
+    (finder.lines, finder.found)
+}
+
+#[test_case]
+fn alloc_and_dealloc_are_traced() {
+    use core::fmt::Write;
+    let value = alloc::boxed::Box::new([0u8; 24]);
+    let mut needle = Line::new();
+    write!(needle, "addr={:p} size=24 align=1 class=", &*value).unwrap();
+    drop(value);
+
+    let (lines, found) = count_lines(needle.as_str());
+    assert!(lines >= 4);
+    assert!(found >= 2);
+    let (_, allocs) = count_lines(" op=alloc ");
+    assert!(allocs > 0);
+}
+
+#[test_case]
+fn trace_keeps_only_recent_events() {
+    for size in 1..2 * blog_os::allocator::TRACE_LEN {
+        drop(alloc::vec::Vec::<u8>::with_capacity(size));
+    }
+    let (lines, _) = count_lines("");
+    assert_eq!(lines, blog_os::allocator::TRACE_LEN + 2);
+    blog_os::ALLOCATOR.dump_trace();
+}
@@ -2,13 +2,17 @@
 #![no_main]
 #![feature(custom_test_frameworks)]
 #![test_runner(blog_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
 
 extern crate blog_os;
+use bootloader::{entry_point, BootInfo};
 use core::panic::PanicInfo;
 
-#[no_mangle]
-pub extern "C" fn _start() -> ! {
-    blog_os::test_main();
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    blog_os::init_heap(boot_info);
+    test_main();
     loop {
         core::hint::spin_loop();
     }
@@ -0,0 +1,73 @@
+//! Banc alloc/free partagé par `tests/alloc_bench.rs` (noyau, sous QEMU) et
+//! `host-tests/tests/alloc_bench.rs` (hôte) : coût en cycles `rdtsc` d'un
+//! couple alloc/free par taille, avec le chemin d'initialisation d'origine
+//! (« avant ») et avec l'allocateur actuel (« après »).
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::fmt;
+
+/// Tailles mesurées : chaque classe par défaut, puis un gros objet.
+pub const SIZES: [usize; 5] = [16, 32, 64, 128, 1024];
+
+/// Référence « avant » : à chaque allocation, le chemin d'origine verrouillait
+/// tour à tour les `SLABS` slabs pour vérifier qu'ils étaient initialisés
+/// (`slab.count == 0`), puis celui de la requête. Ces verrous sont rejoués
+/// avant de déléguer à `inner` ; la libération, qui n'en prenait qu'un, est
+/// déléguée telle quelle.
+pub struct LockedInit<'a, A, const SLABS: usize> {
+    counts: [spin::Mutex<usize>; SLABS],
+    inner: &'a A,
+}
+
+impl<'a, A: GlobalAlloc, const SLABS: usize> LockedInit<'a, A, SLABS> {
+    /// Enveloppe `inner`, tous les slabs vus comme non initialisés.
+    pub fn new(inner: &'a A) -> Self {
+        LockedInit { counts: [const { spin::Mutex::new(0) }; SLABS], inner }
+    }
+}
+
+unsafe impl<A: GlobalAlloc, const SLABS: usize> GlobalAlloc for LockedInit<'_, A, SLABS> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        for count in self.counts.iter() {
+            let mut count = count.lock();
+            if *count == 0 {
+                *count = 1;
+            }
+        }
+        self.inner.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout)
+    }
+}
+
+/// Mesure en cycles `rdtsc` un couple alloc/free de `size` octets, en
+/// moyenne sur `rounds` couples.
+pub fn cycles_per_round(allocator: &impl GlobalAlloc, size: usize, rounds: u64) -> u64 {
+    let layout = Layout::from_size_align(size, 8).unwrap();
+    // Préchauffage : la page du slab est déjà présente pendant la mesure.
+    unsafe { allocator.dealloc(allocator.alloc(layout), layout) };
+
+    let start = unsafe { core::arch::x86_64::_rdtsc() };
+    for _ in 0..rounds {
+        unsafe {
+            let ptr = allocator.alloc(layout);
+            assert!(!ptr.is_null());
+            allocator.dealloc(core::hint::black_box(ptr), layout);
+        }
+    }
+    let end = unsafe { core::arch::x86_64::_rdtsc() };
+    (end - start) / rounds
+}
+
+/// Écrit dans `out` le tableau avant/après, une ligne par taille de `SIZES`.
+pub fn write_table(before: &impl GlobalAlloc, after: &impl GlobalAlloc, rounds: u64, out: &mut dyn fmt::Write) -> fmt::Result {
+    writeln!(out, "bench: size  | before | after (cycles/alloc+free)")?;
+    for &size in SIZES.iter() {
+        let before = cycles_per_round(before, size, rounds);
+        let after = cycles_per_round(after, size, rounds);
+        writeln!(out, "bench: {:>5} | {:>6} | {:>5}", size, before, after)?;
+    }
+    Ok(())
+}
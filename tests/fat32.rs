@@ -2,13 +2,17 @@
 #![no_main]
 #![feature(custom_test_frameworks)]
 #![test_runner(blog_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
 
 extern crate blog_os;
+use bootloader::{entry_point, BootInfo};
 use core::panic::PanicInfo;
 
-#[no_mangle]
-pub extern "C" fn _start() -> ! {
-    blog_os::test_main();
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    blog_os::init_heap(boot_info);
+    test_main();
     loop {
         core::hint::spin_loop();
     }
@@ -37,3 +41,53 @@ fn read_file_test() {
     let data = fs.open_file(&entries[0]).unwrap();
     assert_eq!(core::str::from_utf8(&data).unwrap(), "Hello");
 }
+
+#[test_case]
+fn write_file_test() {
+    let disk = blog_os::fat32::MemoryDisk::new();
+    let mut fs = blog_os::fat32::Fat32::new(disk).unwrap();
+    let root = fs.boot_sector().root_cluster;
+    let mut entry = fs.create_file(root, "KERNEL.LOG").unwrap();
+    fs.write_file(root, &mut entry, b"boot ok").unwrap();
+
+    // Remonte le volume sur le même disque et relit le fichier.
+    let mut fs = blog_os::fat32::Fat32::new(fs.into_device()).unwrap();
+    let entries = fs.read_root_directory().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[1].filename(), "KERNEL.LOG");
+    assert_eq!(fs.open_file(&entries[1]).unwrap(), b"boot ok");
+}
+
+#[test_case]
+fn remove_file_test() {
+    let disk = blog_os::fat32::MemoryDisk::new();
+    let mut fs = blog_os::fat32::Fat32::new(disk).unwrap();
+    fs.remove("/HELLO.TXT").unwrap();
+    assert_eq!(fs.read_fat_entry(3), 0);
+    assert!(fs.read_root_directory().unwrap().is_empty());
+}
+
+#[test_case]
+fn open_path_test() {
+    use blog_os::fat32::FatError;
+    let disk = blog_os::fat32::MemoryDisk::new();
+    let mut fs = blog_os::fat32::Fat32::new(disk).unwrap();
+    assert_eq!(fs.open_path("/./HELLO.TXT").unwrap(), b"Hello");
+    assert!(matches!(fs.open_path("/BOOT/KERNEL.CFG"), Err(FatError::NotFound)));
+    assert!(matches!(fs.open_path("/HELLO.TXT/X"), Err(FatError::NotADirectory)));
+}
+
+#[test_case]
+fn long_name_create_test() {
+    let disk = blog_os::fat32::MemoryDisk::new();
+    let mut fs = blog_os::fat32::Fat32::new(disk).unwrap();
+    let root = fs.boot_sector().root_cluster;
+    let mut entry = fs.create_file(root, "kernel-config.toml").unwrap();
+    assert_eq!(entry.filename(), "KERNEL~1.TOM");
+    fs.write_file(root, &mut entry, b"log = true").unwrap();
+
+    let mut fs = blog_os::fat32::Fat32::new(fs.into_device()).unwrap();
+    let entries = fs.read_root_directory().unwrap();
+    assert_eq!(entries[1].long_name.as_deref(), Some("kernel-config.toml"));
+    assert_eq!(fs.open_path("/Kernel-Config.toml").unwrap(), b"log = true");
+}
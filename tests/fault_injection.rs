@@ -0,0 +1,74 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(blog_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate blog_os;
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    blog_os::init_heap(boot_info);
+    test_main();
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    blog_os::test_panic_handler(info)
+}
+
+extern crate alloc;
+
+/// Tente d'allouer `size` octets ; `true` si l'allocation a réussi.
+fn try_alloc(size: usize) -> bool {
+    let mut buf: alloc::vec::Vec<u8> = alloc::vec::Vec::new();
+    buf.try_reserve_exact(size).is_ok()
+}
+
+#[test_case]
+fn nth_allocation_fails_once() {
+    blog_os::ALLOCATOR.inject_faults(blog_os::allocator::Fault::Nth(3));
+    let results = [try_alloc(24), try_alloc(24), try_alloc(24), try_alloc(24)];
+    assert_eq!(blog_os::ALLOCATOR.clear_faults(), 1);
+    assert_eq!(results, [true, true, false, true]);
+    assert_eq!(blog_os::ALLOCATOR.last_error(), Some(blog_os::allocator::AllocError::Injected));
+}
+
+#[test_case]
+fn every_nth_allocation_fails() {
+    blog_os::ALLOCATOR.inject_faults(blog_os::allocator::Fault::Every(2));
+    let results = [try_alloc(1000), try_alloc(1000), try_alloc(1000), try_alloc(1000)];
+    assert_eq!(blog_os::ALLOCATOR.clear_faults(), 2);
+    assert_eq!(results, [true, false, true, false]);
+}
+
+#[test_case]
+fn size_class_fails() {
+    blog_os::ALLOCATOR.inject_faults(blog_os::allocator::Fault::Class(32));
+    assert!(!try_alloc(20));
+    assert!(try_alloc(100));
+    assert!(try_alloc(1000));
+    blog_os::ALLOCATOR.inject_faults(blog_os::allocator::Fault::Large);
+    assert!(try_alloc(20));
+    assert!(!try_alloc(1000));
+    assert_eq!(blog_os::ALLOCATOR.clear_faults(), 1);
+    assert!(try_alloc(1000));
+}
+
+#[test_case]
+fn growing_vec_hits_injected_fault() {
+    let mut values: alloc::vec::Vec<u32> = alloc::vec::Vec::new();
+    values.try_reserve_exact(4).unwrap();
+    values.extend([1, 2, 3, 4]);
+    blog_os::ALLOCATOR.inject_faults(blog_os::allocator::Fault::Nth(1));
+    assert!(values.try_reserve_exact(1).is_err());
+    assert_eq!(blog_os::ALLOCATOR.clear_faults(), 1);
+    assert_eq!(values, [1, 2, 3, 4]);
+    assert!(values.try_reserve_exact(1).is_ok());
+}
@@ -0,0 +1,90 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(blog_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate blog_os;
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    blog_os::init_heap(boot_info);
+    test_main();
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    blog_os::test_panic_handler(info)
+}
+
+extern crate alloc;
+
+#[test_case]
+#[cfg(not(feature = "buddy"))]
+fn slab_usage_is_tracked() {
+    let before = blog_os::ALLOCATOR.stats();
+    let value = alloc::boxed::Box::new([1u8; 20]);
+    let during = blog_os::ALLOCATOR.stats();
+    assert_eq!(during.slabs[1].obj_size, 32);
+    assert_eq!(during.slabs[1].in_use, before.slabs[1].in_use + 1);
+    assert_eq!(during.slabs[1].bytes_requested, before.slabs[1].bytes_requested + 20);
+    assert!(during.slabs[1].peak_in_use >= during.slabs[1].in_use);
+    drop(value);
+    let after = blog_os::ALLOCATOR.stats();
+    assert_eq!(after.slabs[1].in_use, before.slabs[1].in_use);
+    assert_eq!(after.slabs[1].bytes_requested, before.slabs[1].bytes_requested);
+}
+
+#[test_case]
+#[cfg(not(feature = "buddy"))]
+fn large_usage_is_tracked() {
+    let before = blog_os::ALLOCATOR.stats().large;
+    let buf: alloc::vec::Vec<u8> = alloc::vec::Vec::with_capacity(1000);
+    let during = blog_os::ALLOCATOR.stats().large;
+    assert_eq!(during.in_use, before.in_use + 1);
+    assert_eq!(during.bytes_requested, before.bytes_requested + 1000);
+    assert!(during.free_bytes < before.free_bytes);
+    drop(buf);
+    let after = blog_os::ALLOCATOR.stats().large;
+    assert_eq!(after.in_use, before.in_use);
+    assert_eq!(after.free_bytes, before.free_bytes);
+    assert!(after.peak_in_use > before.in_use);
+}
+
+#[test_case]
+#[cfg(not(feature = "buddy"))]
+fn slab_grows_and_returns_pages() {
+    let before = blog_os::ALLOCATOR.stats().slabs[0];
+    let mut boxes = alloc::vec::Vec::with_capacity(4 * before.total_blocks.max(256));
+    for i in 0..boxes.capacity() {
+        boxes.push(alloc::boxed::Box::new([i as u8; 16]));
+    }
+    let during = blog_os::ALLOCATOR.stats().slabs[0];
+    assert!(during.pages > before.pages);
+    assert!(during.total_blocks >= boxes.len());
+    drop(boxes);
+    #[cfg(feature = "magazines")]
+    blog_os::ALLOCATOR.drain_magazines();
+    let after = blog_os::ALLOCATOR.stats().slabs[0];
+    assert_eq!(after.in_use, before.in_use);
+    assert!(after.pages <= before.pages.max(1));
+}
+
+#[test_case]
+fn print_stats_does_not_panic() {
+    blog_os::ALLOCATOR.print_stats();
+}
+
+#[test_case]
+#[cfg(not(feature = "buddy"))]
+fn dump_map_does_not_panic() {
+    let value = alloc::boxed::Box::new([7u8; 40]);
+    blog_os::ALLOCATOR.dump_map();
+    drop(value);
+}
@@ -0,0 +1,97 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![feature(abi_x86_interrupt)]
+#![test_runner(blog_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate blog_os;
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use lazy_static::lazy_static;
+use x86_64::instructions::interrupts;
+use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    blog_os::init_heap(boot_info);
+    IDT.load();
+    // Toutes les lignes des PIC masquées : aucune IRQ réelle n'arrive sur un
+    // vecteur d'exception une fois les interruptions activées.
+    unsafe {
+        x86_64::instructions::port::Port::<u8>::new(0x21).write(0xff);
+        x86_64::instructions::port::Port::<u8>::new(0xa1).write(0xff);
+    }
+    interrupts::enable();
+    test_main();
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    blog_os::test_panic_handler(info)
+}
+
+extern crate alloc;
+
+lazy_static! {
+    static ref IDT: InterruptDescriptorTable = {
+        let mut idt = InterruptDescriptorTable::new();
+        idt.breakpoint.set_handler_fn(breakpoint_handler);
+        idt
+    };
+}
+
+/// Octets alloués et relus par le dernier gestionnaire.
+static HANDLED: AtomicUsize = AtomicUsize::new(0);
+
+/// Alloue dans chaque classe de slab et un gros objet, puis libère tout.
+extern "x86-interrupt" fn breakpoint_handler(_frame: InterruptStackFrame) {
+    let mut total = 0;
+    for size in [8, 24, 48, 100, 1024] {
+        let block = alloc::vec![0xA5u8; size];
+        total += block.iter().filter(|&&byte| byte == 0xA5).count();
+    }
+    HANDLED.store(total, Ordering::Relaxed);
+}
+
+#[test_case]
+fn handler_can_allocate() {
+    let before = blog_os::ALLOCATOR.snapshot();
+    let boxed = alloc::boxed::Box::new(7u64);
+    interrupts::int3();
+    assert_eq!(HANDLED.load(Ordering::Relaxed), 8 + 24 + 48 + 100 + 1024);
+    assert_eq!(*boxed, 7);
+    drop(boxed);
+    assert!(blog_os::ALLOCATOR.snapshot().leaks_since(&before).is_empty());
+    assert!(interrupts::are_enabled());
+}
+
+/// `write_map` écrit chaque slab sous son verrou : les interruptions doivent
+/// y être masquées, puis rétablies.
+#[test_case]
+#[cfg(not(feature = "buddy"))]
+fn slab_locks_mask_interrupts() {
+    /// Compte les écritures faites interruptions masquées.
+    struct Probe {
+        masked: usize,
+    }
+
+    impl core::fmt::Write for Probe {
+        fn write_str(&mut self, _: &str) -> core::fmt::Result {
+            if !interrupts::are_enabled() {
+                self.masked += 1;
+            }
+            Ok(())
+        }
+    }
+
+    let mut probe = Probe { masked: 0 };
+    blog_os::ALLOCATOR.write_map(&mut probe).unwrap();
+    assert!(probe.masked > 0);
+    assert!(interrupts::are_enabled());
+}
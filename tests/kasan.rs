@@ -0,0 +1,62 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(blog_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate blog_os;
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    blog_os::init_heap(boot_info);
+    test_main();
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    blog_os::test_panic_handler(info)
+}
+
+extern crate alloc;
+
+use alloc::alloc::{alloc, dealloc, Layout};
+use blog_os::allocator::ViolationKind;
+use blog_os::ALLOCATOR;
+
+#[test_case]
+fn live_block_is_accessible() {
+    let buf = alloc::vec![0u8; 100];
+    assert!(ALLOCATOR.check_read(buf.as_ptr(), 100).is_ok());
+    assert!(ALLOCATOR.check_write(buf.as_ptr(), 100).is_ok());
+}
+
+#[test_case]
+fn read_past_slab_block_is_out_of_bounds() {
+    let layout = Layout::from_size_align(20, 4).unwrap();
+    let ptr = unsafe { alloc(layout) };
+    let violation = ALLOCATOR.check_read(ptr, 24).unwrap_err();
+    assert_eq!(violation.kind, ViolationKind::OutOfBounds);
+    assert_eq!(violation.bad_addr, ptr as usize + 20);
+    unsafe { dealloc(ptr, layout) };
+}
+
+#[test_case]
+fn write_after_free_is_reported() {
+    let layout = Layout::from_size_align(2000, 8).unwrap();
+    let ptr = unsafe { alloc(layout) };
+    unsafe { dealloc(ptr, layout) };
+    let violation = ALLOCATOR.check_write(ptr, 8).unwrap_err();
+    assert_eq!(violation.kind, ViolationKind::UseAfterFree);
+}
+
+#[test_case]
+fn memory_outside_the_heap_is_not_checked() {
+    let local = [0u8; 16];
+    assert!(ALLOCATOR.check_read(local.as_ptr(), local.len()).is_ok());
+}
@@ -0,0 +1,52 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate blog_os;
+
+use blog_os::{exit_qemu, serial_println, QemuExitCode};
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+entry_point!(main);
+
+/// Passe à `true` une fois le test équilibré réussi : seul un panic levé
+/// ensuite, par le test qui fuit, est attendu.
+static LEAKING_PHASE: AtomicBool = AtomicBool::new(false);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    blog_os::init_heap(boot_info);
+    balanced_test_passes();
+    LEAKING_PHASE.store(true, Ordering::SeqCst);
+    serial_println!("leak_check::leaking_test_fails...");
+    blog_os::run_checking_leaks(&leaking_test);
+    serial_println!("[test did not panic]");
+    exit_qemu(QemuExitCode::Failed);
+}
+
+fn balanced_test_passes() {
+    serial_println!("leak_check::balanced_test_passes...");
+    blog_os::run_checking_leaks(&balanced_test);
+    serial_println!("[ok]");
+}
+
+fn balanced_test() {
+    let values: alloc::vec::Vec<alloc::boxed::Box<u64>> = (0..100).map(alloc::boxed::Box::new).collect();
+    assert_eq!(values.iter().map(|v| **v).sum::<u64>(), 4950);
+}
+
+fn leaking_test() {
+    core::mem::forget(alloc::boxed::Box::new([0u8; 20]));
+}
+
+/// La fuite doit être détectée : un panic du test qui fuit est un succès,
+/// tout panic antérieur (test équilibré, initialisation) un échec.
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    if !LEAKING_PHASE.load(Ordering::SeqCst) {
+        blog_os::test_panic_handler(info);
+    }
+    serial_println!("[ok] {}", info);
+    exit_qemu(QemuExitCode::Success);
+}
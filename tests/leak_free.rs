@@ -0,0 +1,53 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(blog_os::leak_checking_test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate blog_os;
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    blog_os::init_heap(boot_info);
+    test_main();
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    blog_os::test_panic_handler(info)
+}
+
+extern crate alloc;
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+
+#[test_case]
+fn boxes_are_freed() {
+    let values: Vec<Box<u64>> = (0..100).map(Box::new).collect();
+    assert_eq!(values.iter().map(|v| **v).sum::<u64>(), 4950);
+}
+
+#[test_case]
+fn growing_vec_is_freed() {
+    let mut v = Vec::new();
+    for i in 0..1000u32 {
+        v.push(i);
+    }
+    assert_eq!(v.len(), 1000);
+}
+
+#[test_case]
+fn strings_are_freed() {
+    let mut s = String::new();
+    for word in ["slab", "magazine", "buddy"] {
+        s.push_str(word);
+        s = s.repeat(2);
+    }
+    assert!(s.len() > 20);
+}
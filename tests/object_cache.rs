@@ -0,0 +1,92 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(blog_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate blog_os;
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    blog_os::init_heap(boot_info);
+    test_main();
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    blog_os::test_panic_handler(info)
+}
+
+extern crate alloc;
+
+static DESTROYED: core::sync::atomic::AtomicUsize = core::sync::atomic::AtomicUsize::new(0);
+
+struct Entry {
+    id: usize,
+    name: [u8; 11],
+}
+
+fn new_entry() -> Entry {
+    Entry { id: 0, name: *b"NEW     TXT" }
+}
+
+fn destroy_entry(_: &mut Entry) {
+    DESTROYED.fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+}
+
+static ENTRIES: blog_os::allocator::ObjectCache<Entry> =
+    blog_os::allocator::ObjectCache::new("entry", new_entry).with_destructor(destroy_entry);
+
+#[repr(align(64))]
+struct Aligned([u8; 80]);
+
+static ALIGNED: blog_os::allocator::ObjectCache<Aligned> =
+    blog_os::allocator::ObjectCache::new("aligned", || Aligned([0; 80]));
+
+#[test_case]
+fn cache_constructs_and_destroys_objects() {
+    let destroyed = DESTROYED.load(core::sync::atomic::Ordering::Relaxed);
+    let mut entries = alloc::vec::Vec::new();
+    for id in 0..300 {
+        let mut entry = ENTRIES.alloc().expect("entry cache exhausted");
+        assert_eq!(&entry.name, b"NEW     TXT");
+        entry.id = id;
+        entries.push(entry);
+    }
+    assert!(entries.iter().enumerate().all(|(id, entry)| entry.id == id));
+    let stats = ENTRIES.stats();
+    assert_eq!(stats.in_use, 300);
+    assert!(stats.pages > 1);
+    drop(entries);
+    assert_eq!(DESTROYED.load(core::sync::atomic::Ordering::Relaxed), destroyed + 300);
+    assert_eq!(ENTRIES.stats().in_use, 0);
+}
+
+#[test_case]
+fn cache_respects_alignment() {
+    let objects: alloc::vec::Vec<_> = (0..100).map(|_| ALIGNED.alloc().unwrap()).collect();
+    for object in objects.iter() {
+        assert_eq!(&**object as *const Aligned as usize % 64, 0);
+        assert!(object.0.iter().all(|&b| b == 0));
+    }
+    assert_eq!(ALIGNED.name(), "aligned");
+}
+
+#[test_case]
+fn dropped_cache_returns_its_pages() {
+    let before = blog_os::ALLOCATOR.snapshot();
+    let cache = blog_os::allocator::ObjectCache::new("scratch", new_entry);
+    let entries: alloc::vec::Vec<_> = (0..300).map(|_| cache.alloc().unwrap()).collect();
+    assert!(cache.stats().pages > 1);
+    drop(entries);
+    // La dernière page reste au cache tant qu'il existe.
+    assert_eq!(cache.stats().pages, 1);
+    drop(cache);
+    assert!(blog_os::ALLOCATOR.snapshot().leaks_since(&before).is_empty());
+}
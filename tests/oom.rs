@@ -1,14 +1,19 @@
 #![no_std]
 #![no_main]
 #![feature(custom_test_frameworks)]
+#![cfg_attr(feature = "oom_integration", feature(alloc_error_handler))]
 #![test_runner(blog_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
 
 extern crate blog_os;
+use bootloader::{entry_point, BootInfo};
 use core::panic::PanicInfo;
 
-#[no_mangle]
-pub extern "C" fn _start() -> ! {
-    blog_os::test_main();
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    blog_os::init_heap(boot_info);
+    test_main();
     loop {
         core::hint::spin_loop();
     }
@@ -21,14 +26,84 @@ fn panic(info: &PanicInfo) -> ! {
 
 extern crate alloc;
 
+/// Taille des morceaux du cache libérable.
+const CHUNK: usize = 16 * 1024;
+
+/// Cache libérable sous pression mémoire.
+static CACHE: spin::Mutex<alloc::vec::Vec<alloc::vec::Vec<u8>>> = spin::Mutex::new(alloc::vec::Vec::new());
+
+static DROP_CACHE: blog_os::allocator::Shrinker =
+    blog_os::allocator::Shrinker { name: "cache", shrink: drop_cache };
+static NOTHING: blog_os::allocator::Shrinker =
+    blog_os::allocator::Shrinker { name: "nothing", shrink: |_| 0 };
+
+fn drop_cache(_: &alloc::alloc::Layout) -> usize {
+    let mut cache = CACHE.lock();
+    let reclaimed = cache.iter().map(|chunk| chunk.capacity()).sum();
+    cache.clear();
+    reclaimed
+}
+
+fn register_shrinkers() {
+    static REGISTERED: spin::Once<()> = spin::Once::new();
+    REGISTERED.call_once(|| {
+        blog_os::ALLOCATOR.register_shrinker(&DROP_CACHE).unwrap();
+        blog_os::ALLOCATOR.register_shrinker(&NOTHING).unwrap();
+    });
+}
+
+/// L'OOM n'est attendu qu'après une passe de tous les shrinkers, pour une
+/// requête plus grande que le heap.
 #[cfg(feature = "oom_integration")]
 #[alloc_error_handler]
-fn on_oom(_layout: alloc::alloc::Layout) -> ! {
-    blog_os::exit_qemu(blog_os::QemuExitCode::Success);
+fn on_oom(layout: alloc::alloc::Layout) -> ! {
+    let error = blog_os::ALLOCATOR.last_error();
+    blog_os::serial_println!("out of memory: {:?}: {:?}", layout, error);
+    let mut all_ran = error == Some(blog_os::allocator::AllocError::TooLarge);
+    for stats in blog_os::ALLOCATOR.shrinker_stats().iter().flatten() {
+        blog_os::serial_println!("shrinker {}: {} runs, {} bytes", stats.name, stats.runs, stats.reclaimed);
+        all_ran &= stats.runs > 0;
+    }
+    if all_ran {
+        blog_os::exit_qemu(blog_os::QemuExitCode::Success);
+    }
+    blog_os::exit_qemu(blog_os::QemuExitCode::Failed);
+}
+
+#[test_case]
+fn large_allocation_fits() {
+    // 1 KiB dépasse le plus grand slab : servi par l'allocateur de gros objets.
+    let buf: alloc::boxed::Box<[u8; 1024]> = alloc::boxed::Box::new([0; 1024]);
+    assert_eq!(buf.len(), 1024);
+}
+
+#[test_case]
+fn shrinkers_make_room_before_oom() {
+    // Le cache remplit le heap avant l'enregistrement des shrinkers : la
+    // dernière allocation ne réussit qu'une fois le cache rendu.
+    let mut cache = CACHE.lock();
+    cache.reserve_exact(2 * blog_os::KERNEL_HEAP_MAX / CHUNK);
+    loop {
+        let mut chunk = alloc::vec::Vec::new();
+        if chunk.try_reserve_exact(CHUNK).is_err() {
+            break;
+        }
+        cache.push(chunk);
+    }
+    drop(cache);
+
+    register_shrinkers();
+    let buf: alloc::vec::Vec<u8> = alloc::vec::Vec::with_capacity(CHUNK);
+    assert!(CACHE.lock().is_empty());
+    let stats = blog_os::ALLOCATOR.shrinker_stats();
+    assert!(stats.iter().flatten().any(|s| s.name == "cache" && s.reclaimed >= CHUNK));
+    drop(buf);
 }
 
 #[test_case]
 fn test_oom() {
-    let _buf: alloc::boxed::Box<[u8; 1024]> = alloc::boxed::Box::new([0; 1024]);
+    register_shrinkers();
+    // Plus grand que le heap entier : déclenche forcément l'OOM.
+    let _buf: alloc::vec::Vec<u8> = alloc::vec::Vec::with_capacity(2 * blog_os::KERNEL_HEAP_MAX);
     blog_os::exit_qemu(blog_os::QemuExitCode::Failed);
 }
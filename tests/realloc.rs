@@ -0,0 +1,72 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(blog_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate blog_os;
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    blog_os::init_heap(boot_info);
+    test_main();
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    blog_os::test_panic_handler(info)
+}
+
+extern crate alloc;
+
+#[test_case]
+fn realloc_stays_in_place_within_class() {
+    use core::alloc::GlobalAlloc;
+    let layout = core::alloc::Layout::from_size_align(17, 8).unwrap();
+    unsafe {
+        let ptr = blog_os::ALLOCATOR.alloc(layout);
+        ptr.write_bytes(0x5A, 17);
+        let grown = blog_os::ALLOCATOR.realloc(ptr, layout, 24);
+        assert_eq!(grown, ptr);
+        let shrunk = blog_os::ALLOCATOR.realloc(grown, core::alloc::Layout::from_size_align(24, 8).unwrap(), 9);
+        assert_eq!(shrunk, ptr);
+        assert!((0..9).all(|i| *shrunk.add(i) == 0x5A));
+        blog_os::ALLOCATOR.dealloc(shrunk, core::alloc::Layout::from_size_align(9, 8).unwrap());
+    }
+}
+
+#[test_case]
+#[cfg(not(feature = "buddy"))]
+fn realloc_moves_across_classes() {
+    let mut v: alloc::vec::Vec<u32> = (0..4).collect();
+    let ptr = v.as_ptr();
+    v.extend(4..64);
+    assert_ne!(v.as_ptr(), ptr);
+    assert!(v.iter().copied().eq(0..64));
+}
+
+#[test_case]
+fn large_realloc_shrinks_in_place() {
+    let mut v: alloc::vec::Vec<u8> = alloc::vec::Vec::with_capacity(3000);
+    v.extend_from_slice(&[7; 600]);
+    let ptr = v.as_ptr();
+    v.shrink_to_fit();
+    assert_eq!(v.as_ptr(), ptr);
+    assert!(v.iter().all(|&b| b == 7));
+}
+
+#[test_case]
+fn alloc_zeroed_clears_recycled_blocks() {
+    for size in [24, 100, 2000] {
+        let dirty = alloc::vec![0xFFu8; size];
+        drop(dirty);
+        let zeroed = alloc::vec![0u8; size];
+        assert!(zeroed.iter().all(|&b| b == 0));
+    }
+}
@@ -1,38 +1,41 @@
 #![no_std]
 #![no_main]
-#![feature(custom_test_frameworks)]
-#![test_runner(blog_os::test_runner)]
 
+extern crate alloc;
 extern crate blog_os;
+
+use blog_os::{exit_qemu, serial_println, QemuExitCode};
+use bootloader::{entry_point, BootInfo};
 use core::panic::PanicInfo;
 
-#[no_mangle]
-pub extern "C" fn _start() -> ! {
-    blog_os::test_main();
-    loop {
-        core::hint::spin_loop();
-    }
-}
+entry_point!(main);
 
-#[panic_handler]
-fn panic(info: &PanicInfo) -> ! {
-    blog_os::test_panic_handler(info)
+fn main(boot_info: &'static BootInfo) -> ! {
+    blog_os::init_heap(boot_info);
+    invalid_cluster_panics();
+    serial_println!("[test did not panic]");
+    exit_qemu(QemuExitCode::Failed);
 }
 
-
-#[test_case]
 fn invalid_cluster_panics() {
-    blog_os::serial_println!("should_panic::invalid_cluster...");
+    serial_println!("should_panic::invalid_cluster...");
     let disk = blog_os::fat32::MemoryDisk::new();
     let mut fs = blog_os::fat32::Fat32::new(disk).expect("fs");
-    // invalid file with cluster 99 to trigger panic
+    // invalid file with cluster 99: `open_file` reports `InvalidCluster`,
+    // and `unwrap` turns it into the expected panic
     let entry = blog_os::fat32::DirectoryEntry {
         name: *b"BADFILE BIN", // 8.3 filename padded to 11 bytes
         attr: 0x20,
         first_cluster: 99,
         size: 1,
+        long_name: None,
     };
-    let _ = fs.open_file(&entry);
-    blog_os::serial_println!("[test did not panic]");
-    blog_os::exit_qemu(blog_os::QemuExitCode::Failed);
+    fs.open_file(&entry).unwrap();
+}
+
+/// Le cluster invalide doit être détecté : un panic est ici un succès.
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    serial_println!("[ok] {}", info);
+    exit_qemu(QemuExitCode::Success);
 }
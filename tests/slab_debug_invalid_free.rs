@@ -0,0 +1,37 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate blog_os;
+
+use alloc::alloc::{alloc, dealloc, Layout};
+use blog_os::{exit_qemu, serial_println, QemuExitCode};
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    blog_os::init_heap(boot_info);
+    interior_pointer_free_panics();
+    serial_println!("[test did not panic]");
+    exit_qemu(QemuExitCode::Failed);
+}
+
+fn interior_pointer_free_panics() {
+    serial_println!("slab_debug_invalid_free::interior_pointer_free_panics...");
+    let layout = Layout::from_size_align(24, 8).unwrap();
+    unsafe {
+        let ptr = alloc(layout);
+        assert!(!ptr.is_null());
+        // Pointeur intérieur au bloc : ce n'est pas un début de bloc.
+        dealloc(ptr.add(8), layout);
+    }
+}
+
+/// La libération d'un pointeur intérieur doit être détectée : un panic est ici un succès.
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    serial_println!("[ok] {}", info);
+    exit_qemu(QemuExitCode::Success);
+}
@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate blog_os;
+
+use alloc::alloc::{alloc, dealloc, Layout};
+use blog_os::{exit_qemu, serial_println, QemuExitCode};
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    blog_os::init_heap(boot_info);
+    red_zone_overwrite_panics();
+    serial_println!("[test did not panic]");
+    exit_qemu(QemuExitCode::Failed);
+}
+
+fn red_zone_overwrite_panics() {
+    serial_println!("slab_debug_redzone::red_zone_overwrite_panics...");
+    // 20 octets dans un bloc de 32 : les octets 20..32 forment la zone rouge.
+    let layout = Layout::from_size_align(20, 4).unwrap();
+    unsafe {
+        let ptr = alloc(layout);
+        assert!(!ptr.is_null());
+        ptr.add(20).write_volatile(0);
+        dealloc(ptr, layout);
+    }
+}
+
+/// Le débordement dans la zone rouge doit être détecté : un panic est ici un succès.
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    serial_println!("[ok] {}", info);
+    exit_qemu(QemuExitCode::Success);
+}
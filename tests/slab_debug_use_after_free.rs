@@ -0,0 +1,41 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate blog_os;
+
+use alloc::alloc::{alloc, dealloc, Layout};
+use blog_os::{exit_qemu, serial_println, QemuExitCode};
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    blog_os::init_heap(boot_info);
+    write_after_free_panics();
+    serial_println!("[test did not panic]");
+    exit_qemu(QemuExitCode::Failed);
+}
+
+fn write_after_free_panics() {
+    serial_println!("slab_debug_use_after_free::write_after_free_panics...");
+    let layout = Layout::from_size_align(48, 8).unwrap();
+    unsafe {
+        let ptr = alloc(layout);
+        assert!(!ptr.is_null());
+        dealloc(ptr, layout);
+        // Écriture dans le bloc libre, après son lien de liste libre : le
+        // poison est vérifié quand le bloc est redistribué.
+        ptr.add(16).write_volatile(0);
+        let again = alloc(layout);
+        serial_println!("block {:p} handed out again as {:p}", ptr, again);
+    }
+}
+
+/// L'écriture après libération doit être détectée : un panic est ici un succès.
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    serial_println!("[ok] {}", info);
+    exit_qemu(QemuExitCode::Success);
+}